@@ -1,30 +1,449 @@
 extern crate berust;
 
-use berust::interpreter::{Interpreter, StdInputOutput};
-use berust::playfield::Playfield;
+use berust::interpreter::{self, InputOutput, Interpreter, StdInputOutput, StepError, StepOutcome};
+use berust::playfield::{self, Playfield};
+use berust::sandbox::{self, SandboxOutcome};
 use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
+/// The line separating the program from its input in a bundled file
+pub const INPUT_MARKER: &str = "---INPUT---";
+
+/// The maximum number of steps of each program in batch mode
+pub const BATCH_MAX_STEPS: usize = 1_000_000;
+
+/// The command line options
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    file: String,
+    dump_stack: bool,
+    echo_input: bool,
+    bundled: bool,
+    batch: bool,
+    step: bool,
+    ascii_output_only: bool,
+    max_size: Option<u64>,
+}
+
+impl Options {
+    /// Parse the options from the given command line arguments.
+    ///
+    /// Returns `None` if the arguments are invalid.
+    pub fn parse(args: &[String]) -> Option<Self> {
+        let mut options = Self::default();
+        let mut file = None;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dump-stack" => options.dump_stack = true,
+                "--echo-input" => options.echo_input = true,
+                "--bundled" => options.bundled = true,
+                "--batch" => options.batch = true,
+                "--step" => options.step = true,
+                "--ascii-output-only" | "--chars-only" => options.ascii_output_only = true,
+                "--max-size" => options.max_size = Some(args.next()?.parse().ok()?),
+                _ if file.is_none() => file = Some(arg.clone()),
+                _ => return None,
+            }
+        }
+
+        options.file = file?;
+
+        Some(options)
+    }
+}
+
+/// Split a bundled file into the program and its input.
+///
+/// The program ends before the first line consisting only of [`INPUT_MARKER`] and the input
+/// starts after it. If there is no such line, the whole file is the program and the input is
+/// empty.
+///
+/// [`INPUT_MARKER`]: constant.INPUT_MARKER.html
+pub fn split_bundled(source: &str) -> (&str, &str) {
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        if line.trim_end_matches(&['\n', '\r'][..]) == INPUT_MARKER {
+            return (&source[..offset], &source[offset + line.len()..]);
+        }
+
+        offset += line.len();
+    }
+
+    (source, "")
+}
+
+/// Run the given program with empty input for at most `max_steps` steps.
+///
+/// Returns the outcome in the same form as a sandboxed run together with the number of
+/// executed steps and the length of the captured output.
+pub fn run_captured(playfield: Playfield, max_steps: usize) -> (SandboxOutcome, usize, usize) {
+    let io = InputOutput::new(&[][..], Vec::new());
+    let mut interpreter = Interpreter::new(playfield, io);
+
+    let outcome = sandbox::run_limited(&mut interpreter, max_steps);
+
+    (
+        outcome,
+        interpreter.steps(),
+        interpreter.io().writer().len(),
+    )
+}
+
+/// Format the summary line of a single program in batch mode.
+pub fn format_batch_result(
+    file: &Path,
+    outcome: &SandboxOutcome,
+    steps: usize,
+    output_len: usize,
+) -> String {
+    let status = match outcome {
+        SandboxOutcome::Terminated { .. } => "terminated".to_string(),
+        SandboxOutcome::StepLimitReached => "step-limited".to_string(),
+        SandboxOutcome::Failed(err) => format!("errored ({})", err),
+    };
+
+    format!(
+        "{}: {} steps={} output={}",
+        file.display(),
+        status,
+        steps,
+        output_len
+    )
+}
+
+/// Run every `.bf` file in the given directory and print one summary line per file.
+///
+/// Files with more than `max_size` bytes are reported without being run.
+fn run_batch(dir: &str, max_size: Option<u64>) -> io::Result<()> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bf"))
+        .collect();
+
+    files.sort();
+
+    for file in files {
+        match Playfield::from_path_with_limit(&file, max_size) {
+            Ok(playfield) => {
+                let (outcome, steps, output_len) = run_captured(playfield, BATCH_MAX_STEPS);
+
+                println!(
+                    "{}",
+                    format_batch_result(&file, &outcome, steps, output_len)
+                );
+            }
+            Err(e) => println!("{}", e.describe(&file)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute the program one step per line read from `keys`, printing the state to `out`.
+///
+/// Before each step, the playfield with the current position marked and the stack are
+/// printed. Stepping ends when the program terminates, a step fails or `keys` is exhausted.
+pub fn step_through<R, W, K, O>(
+    interpreter: &mut Interpreter<R, W>,
+    mut keys: K,
+    out: &mut O,
+) -> io::Result<Result<StepOutcome, StepError>>
+where
+    R: BufRead,
+    W: Write,
+    K: BufRead,
+    O: Write,
+{
+    let mut line = String::new();
+
+    loop {
+        writeln!(
+            out,
+            "{}Stack: {}",
+            interpreter
+                .field()
+                .render_with_cursor(interpreter.nav().pos()),
+            interpreter::format_stack(interpreter.stack())
+        )?;
+        out.flush()?;
+
+        line.clear();
+
+        if keys.read_line(&mut line)? == 0 {
+            return Ok(Ok(StepOutcome::Continue));
+        }
+
+        match interpreter.try_step() {
+            Ok(StepOutcome::Terminated) => return Ok(Ok(StepOutcome::Terminated)),
+            Ok(_) => (),
+            Err(e) => return Ok(Err(e)),
+        }
+    }
+}
+
+/// Apply the input and output related options.
+///
+/// With `--ascii-output-only` (or its older name `--chars-only`), integers written by `.` are
+/// discarded, so only the characters written by `,` are part of the output.
+pub fn configure_io<R: BufRead, W: Write>(io: &mut InputOutput<R, W>, options: &Options) {
+    io.set_echo(options.echo_input);
+    io.set_discard_ints(options.ascii_output_only);
+}
+
+/// Format an error of a failed step together with the position of the instruction.
+pub fn format_step_error(err: &StepError) -> String {
+    let (x, y) = err.pos();
+
+    format!("{} at ({}, {})", err, x, y)
+}
+
+/// Run the program to completion and dump the stack if requested.
+///
+/// If a step fails, the error is printed and the process exits with a nonzero status after
+/// the stack has been dumped.
+fn run<R: BufRead>(playfield: Playfield, mut io: InputOutput<R, io::Stdout>, options: &Options) {
+    configure_io(&mut io, options);
+
+    let mut interpreter = Interpreter::new(playfield, io);
+
+    let result = if options.step {
+        let stdin = io::stdin();
+
+        step_through(&mut interpreter, stdin.lock(), &mut io::stderr()).unwrap_or_else(|e| {
+            eprintln!("could not step: {}", e);
+
+            process::exit(1);
+        })
+    } else {
+        interpreter.run_with_limit(usize::MAX)
+    };
+
+    if let Err(e) = &result {
+        eprintln!("{}", format_step_error(e));
+    }
+
+    if options.dump_stack {
+        eprintln!("{}", stack_to_json(interpreter.stack()));
+    }
+
+    if result.is_err() {
+        process::exit(1);
+    }
+}
+
+/// Format the given stack as a JSON array.
+pub fn stack_to_json(stack: &[i64]) -> String {
+    let values: Vec<String> = stack.iter().map(i64::to_string).collect();
+
+    format!("[{}]", values.join(","))
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        println!("Usage: ./befunge <file>");
+    let options = match Options::parse(&args[1..]) {
+        Some(options) => options,
+        None => {
+            println!(
+                "Usage: ./befunge [--dump-stack] [--echo-input] [--bundled] [--step] [--ascii-output-only] [--max-size <bytes>] <file>"
+            );
+            println!("       ./befunge --batch [--max-size <bytes>] <dir>");
+            println!();
+            println!("  --ascii-output-only  Drop the integers written by ., only the characters written by , are output");
+
+            process::exit(1);
+        }
+    };
+
+    if options.batch {
+        if let Err(e) = run_batch(&options.file, options.max_size) {
+            eprintln!("could not read {}: {}", options.file, e);
+
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    let source = playfield::read_program(&options.file, options.max_size).unwrap_or_else(|e| {
+        eprintln!("{}", e.describe(&options.file));
 
         process::exit(1);
+    });
+
+    // In step mode, stdin is used for stepping, so the program gets its input from the
+    // bundled file or no input at all
+    if options.bundled || options.step {
+        let (program, input) = if options.bundled {
+            split_bundled(&source)
+        } else {
+            (source.as_str(), "")
+        };
+        let io = InputOutput::new(input.as_bytes(), io::stdout());
+
+        run(Playfield::new(program), io, &options);
+    } else {
+        run(Playfield::new(&source), StdInputOutput::default(), &options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(None, Options::parse(&args(&[])));
+        assert_eq!(None, Options::parse(&args(&["a.bf", "b.bf"])));
+
+        let options = Options::parse(&args(&["--dump-stack", "a.bf"])).unwrap();
+
+        assert_eq!("a.bf", options.file);
+        assert!(options.dump_stack);
+        assert!(!options.echo_input);
+
+        let options = Options::parse(&args(&["a.bf", "--echo-input"])).unwrap();
+
+        assert!(options.echo_input);
+        assert!(!options.bundled);
+        assert_eq!(None, options.max_size);
+
+        let options = Options::parse(&args(&["--bundled", "a.bf"])).unwrap();
+
+        assert!(options.bundled);
+        assert!(!options.batch);
+
+        let options = Options::parse(&args(&["--step", "a.bf"])).unwrap();
+
+        assert!(options.step);
+
+        let options = Options::parse(&args(&["--batch", "programs"])).unwrap();
+
+        assert!(options.batch);
+        assert_eq!("programs", options.file);
+
+        let options = Options::parse(&args(&["--max-size", "100", "a.bf"])).unwrap();
+
+        assert_eq!("a.bf", options.file);
+        assert_eq!(Some(100), options.max_size);
+        assert_eq!(None, Options::parse(&args(&["a.bf", "--max-size"])));
+        assert_eq!(None, Options::parse(&args(&["--max-size", "x", "a.bf"])));
+    }
+
+    #[test]
+    fn split_bundled_file() {
+        assert_eq!(("&.@\n", ""), split_bundled("&.@\n"));
+        assert_eq!(("&.@\n", "42\n"), split_bundled("&.@\n---INPUT---\n42\n"));
+        assert_eq!(("&.@\r\n", "42"), split_bundled("&.@\r\n---INPUT---\r\n42"));
+        assert_eq!(("", ""), split_bundled("---INPUT---"));
+        assert_eq!(("a ---INPUT---\n", ""), split_bundled("a ---INPUT---\n"));
+    }
+
+    #[test]
+    fn batch_result() {
+        let file = Path::new("dir/a.bf");
+
+        assert_eq!(
+            "dir/a.bf: terminated steps=4 output=2",
+            format_batch_result(file, &SandboxOutcome::Terminated { steps: 4 }, 4, 2)
+        );
+        assert_eq!(
+            "dir/a.bf: step-limited steps=100 output=0",
+            format_batch_result(file, &SandboxOutcome::StepLimitReached, 100, 0)
+        );
+
+        let err = StepError::IllegalInstruction {
+            pos: (1, 0),
+            instruction: b'x',
+        };
+
+        assert_eq!(
+            "dir/a.bf: errored (Illegal character: x) steps=1 output=0",
+            format_batch_result(file, &SandboxOutcome::Failed(err), 1, 0)
+        );
+    }
+
+    #[test]
+    fn batch_run_captured() {
+        let (outcome, steps, output_len) = run_captured(Playfield::new("23*.@"), 100);
+
+        assert_eq!(SandboxOutcome::Terminated { steps: 5 }, outcome);
+        assert_eq!(5, steps);
+        assert_eq!(2, output_len);
+
+        let (outcome, steps, _) = run_captured(Playfield::new(">"), 100);
+
+        assert_eq!(SandboxOutcome::StepLimitReached, outcome);
+        assert_eq!(100, steps);
     }
 
-    let mut file = File::open(&args[1]).unwrap();
-    let mut contents = String::new();
+    #[test]
+    fn step_through_program() {
+        let io = InputOutput::new(&[][..], Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("23*.@"), io);
+        let mut out = Vec::new();
 
-    file.read_to_string(&mut contents).unwrap();
+        let outcome = step_through(&mut interpreter, &b"\n\n\n\n\n\n\n"[..], &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
 
-    let playfield = Playfield::new(&contents);
-    let io = StdInputOutput::default();
+        assert_eq!(Ok(StepOutcome::Terminated), outcome);
+        assert_eq!(b"6 ", &interpreter.io().writer()[..]);
+        assert_eq!(5, out.matches("Stack: ").count());
+        assert!(out.starts_with("23*.@\n^\nStack: []\n"));
+        assert!(out.contains("23*.@\n  ^\nStack: [2, 3]\n"));
 
-    let interpreter = Interpreter::new(playfield, io);
+        let io = InputOutput::new(&[][..], Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("23*.@"), io);
 
-    for _ in interpreter {}
+        let outcome = step_through(&mut interpreter, &b"\n"[..], &mut Vec::new()).unwrap();
+
+        assert_eq!(Ok(StepOutcome::Continue), outcome);
+        assert_eq!(1, interpreter.steps());
+
+        let io = InputOutput::new(&[][..], Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("1x@"), io);
+
+        let outcome = step_through(&mut interpreter, &b"\n\n\n"[..], &mut Vec::new()).unwrap();
+        let err = StepError::IllegalInstruction {
+            pos: (1, 0),
+            instruction: b'x',
+        };
+
+        assert_eq!(Err(err), outcome);
+        assert_eq!("Illegal character: x at (1, 0)", format_step_error(&err));
+    }
+
+    #[test]
+    fn ascii_output_only() {
+        let run = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            let options = Options::parse(&args).unwrap();
+            let mut io = InputOutput::new(&[][..], Vec::new());
+
+            configure_io(&mut io, &options);
+
+            let mut interpreter = Interpreter::new(Playfield::new("\"iH\",,55+:.,@"), io);
+
+            interpreter.run_to_completion();
+            interpreter.io().writer().clone()
+        };
+
+        assert_eq!(b"Hi10 \n".to_vec(), run(&["a.bf"]));
+        assert_eq!(b"Hi\n".to_vec(), run(&["--ascii-output-only", "a.bf"]));
+        assert_eq!(b"Hi\n".to_vec(), run(&["--chars-only", "a.bf"]));
+    }
+
+    #[test]
+    fn format_stack_json() {
+        assert_eq!("[]", stack_to_json(&[]));
+        assert_eq!("[1,-2,30]", stack_to_json(&[1, -2, 30]));
+    }
 }
@@ -0,0 +1,214 @@
+extern crate berust;
+
+use berust::interpreter::{Interpreter, NullInputOutput, StepError};
+use berust::playfield::Playfield;
+use std::env;
+use std::process;
+use std::time::{Duration, Instant};
+
+/// The command line options
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    file: String,
+    iters: usize,
+    max_size: Option<u64>,
+}
+
+impl Options {
+    /// Parse the options from the given command line arguments.
+    ///
+    /// Returns `None` if the arguments are invalid or the number of iterations is zero.
+    pub fn parse(args: &[String]) -> Option<Self> {
+        let mut file = None;
+        let mut iters = 1;
+        let mut max_size = None;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--iters" => iters = args.next()?.parse().ok().filter(|&n| n > 0)?,
+                "--max-size" => max_size = Some(args.next()?.parse().ok()?),
+                _ if file.is_none() && !arg.starts_with("--") => file = Some(arg.clone()),
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            file: file?,
+            iters,
+            max_size,
+        })
+    }
+}
+
+/// The measurements of a single benchmark run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+    steps: usize,
+    time: Duration,
+    max_stack_depth: usize,
+}
+
+impl Measurement {
+    /// Return the number of executed steps per second.
+    pub fn steps_per_sec(&self) -> f64 {
+        let secs = self.time.as_secs_f64();
+
+        if secs > 0.0 {
+            self.steps as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Compute the average of the given measurements.
+///
+/// Returns `None` if there are no measurements.
+pub fn average(runs: &[Measurement]) -> Option<Measurement> {
+    if runs.is_empty() {
+        return None;
+    }
+
+    let n = runs.len();
+
+    Some(Measurement {
+        steps: runs.iter().map(|m| m.steps).sum::<usize>() / n,
+        time: runs
+            .iter()
+            .map(|m| m.time)
+            .sum::<Duration>()
+            .div_f64(n as f64),
+        max_stack_depth: runs.iter().map(|m| m.max_stack_depth).max().unwrap(),
+    })
+}
+
+/// Run the given program once with empty input and discarded output.
+///
+/// Returns the error if a step of the program fails.
+pub fn measure(playfield: &Playfield) -> Result<Measurement, StepError> {
+    let playfield = playfield.clone();
+    let mut interpreter = Interpreter::new(playfield, NullInputOutput::default());
+
+    let start = Instant::now();
+    interpreter.run_with_limit(usize::MAX)?;
+    let time = start.elapsed();
+
+    Ok(Measurement {
+        steps: interpreter.steps(),
+        time,
+        max_stack_depth: interpreter.max_stack_depth(),
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let options = match Options::parse(&args[1..]) {
+        Some(options) => options,
+        None => {
+            println!("Usage: ./bench [--iters <n>] [--max-size <bytes>] <file>");
+
+            process::exit(1);
+        }
+    };
+
+    let playfield = Playfield::from_path_with_limit(&options.file, options.max_size)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e.describe(&options.file));
+
+            process::exit(1);
+        });
+
+    let runs: Vec<Measurement> = (0..options.iters)
+        .map(|_| measure(&playfield))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| {
+            let (x, y) = e.pos();
+
+            eprintln!("{} at ({}, {})", e, x, y);
+
+            process::exit(1);
+        });
+    let avg = average(&runs).unwrap();
+
+    println!("iters {}", options.iters);
+    println!("steps {}", avg.steps);
+    println!("time_ms {:.3}", avg.time.as_secs_f64() * 1000.0);
+    println!("steps_per_sec {:.0}", avg.steps_per_sec());
+    println!("max_stack_depth {}", avg.max_stack_depth);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            Some(Options {
+                file: "a.bf".to_string(),
+                iters: 1,
+                max_size: None,
+            }),
+            Options::parse(&args(&["a.bf"]))
+        );
+        assert_eq!(
+            Some(Options {
+                file: "a.bf".to_string(),
+                iters: 5,
+                max_size: Some(100),
+            }),
+            Options::parse(&args(&["--iters", "5", "a.bf", "--max-size", "100"]))
+        );
+
+        assert_eq!(None, Options::parse(&args(&[])));
+        assert_eq!(None, Options::parse(&args(&["a.bf", "b.bf"])));
+        assert_eq!(None, Options::parse(&args(&["--verbose", "a.bf"])));
+        assert_eq!(None, Options::parse(&args(&["a.bf", "--iters"])));
+        assert_eq!(None, Options::parse(&args(&["--iters", "0", "a.bf"])));
+        assert_eq!(None, Options::parse(&args(&["--max-size", "x", "a.bf"])));
+    }
+
+    #[test]
+    fn average_measurements() {
+        assert_eq!(None, average(&[]));
+
+        let runs = [
+            Measurement {
+                steps: 10,
+                time: Duration::from_millis(100),
+                max_stack_depth: 3,
+            },
+            Measurement {
+                steps: 10,
+                time: Duration::from_millis(300),
+                max_stack_depth: 5,
+            },
+        ];
+
+        let avg = average(&runs).unwrap();
+
+        assert_eq!(10, avg.steps);
+        assert_eq!(Duration::from_millis(200), avg.time);
+        assert_eq!(5, avg.max_stack_depth);
+        assert_eq!(50.0, avg.steps_per_sec());
+    }
+
+    #[test]
+    fn measure_program() {
+        let m = measure(&Playfield::new("123$$$@")).unwrap();
+
+        assert_eq!(7, m.steps);
+        assert_eq!(3, m.max_stack_depth);
+        assert_eq!(
+            Err(StepError::IllegalInstruction {
+                pos: (1, 0),
+                instruction: b'x',
+            }),
+            measure(&Playfield::new("1x@"))
+        );
+    }
+}
@@ -1,20 +1,25 @@
 extern crate berust;
 extern crate tui;
 
-use berust::interpreter::{InputOutput, Interpreter, Stack};
-use berust::playfield::Playfield;
-use std::fs::File;
-use std::io::{Cursor, Read};
+use berust::instruction::{instr, instruction_set, Dialect, InstructionKind};
+use berust::interpreter::{
+    self, InputOutput, Interpreter, Mode, Stack, StepEffect, StepError, StepOutcome,
+};
+use berust::playfield::{self, Playfield};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use std::{cmp, env, io, iter, process, str, thread};
+use std::{cmp, env, fs, io, iter, process, str, thread};
 use termion::event::Key;
 use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
 use termion::screen::AlternateScreen;
 use tui::backend::TermionBackend;
-use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::buffer::Buffer;
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Style};
 use tui::widgets::{Block, Borders, Paragraph, Text, Widget};
 use tui::Terminal;
@@ -50,10 +55,8 @@ impl Events {
             thread::spawn(move || {
                 let stdin = io::stdin();
 
-                for result in stdin.keys() {
-                    if let Ok(key) = result {
-                        sender.send(Event::Input(key)).unwrap();
-                    }
+                for key in stdin.keys().flatten() {
+                    sender.send(Event::Input(key)).unwrap();
                 }
             });
         }
@@ -79,13 +82,193 @@ impl Events {
 
 /// A message for the runtime environment
 ///
-/// One can tell the runtime to pause or resume, to proceed slower or faster,
-/// and, if paused, to execute a single step.
+/// One can tell the runtime to pause or resume, to proceed slower or faster
+/// or at an exact number of steps per second, to run until the program ends,
+/// and, if paused, to execute or undo a single step. Slowing down at branches can be
+/// toggled as well.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RuntimeCommand {
     TogglePause,
+    Pause,
+    ToggleSlowBranches,
     Slower,
     Faster,
+    SetRate(u32),
+    RunToEnd,
     Step,
+    StepBack,
+}
+
+/// The state of the runtime environment
+///
+/// It keeps track of whether the interpreter is running and how long to wait between two steps.
+#[derive(Debug, PartialEq)]
+pub struct RuntimeState {
+    delay: u64,
+    running: bool,
+    fast_forward: bool,
+    slow_branches: bool,
+    at_branch: bool,
+    last_step: Option<StepOutcome>,
+    error: Option<StepError>,
+}
+
+impl RuntimeState {
+    /// The minimum delay between two steps in milliseconds
+    const MIN_DELAY: u64 = 10;
+
+    /// The maximum delay between two steps in milliseconds
+    const MAX_DELAY: u64 = 1000;
+
+    /// The maximum number of steps per tick when fast-forwarding
+    ///
+    /// This keeps the interpreter lock short so the user interface stays responsive.
+    const FAST_FORWARD_STEPS: usize = 10_000;
+
+    /// The factor by which the delay grows while the pointer waits on a branch instruction
+    const BRANCH_SLOWDOWN: u64 = 5;
+
+    /// Create a paused runtime state with a delay of 100ms.
+    pub fn new() -> Self {
+        Self {
+            delay: 100,
+            running: false,
+            fast_forward: false,
+            slow_branches: false,
+            at_branch: false,
+            last_step: None,
+            error: None,
+        }
+    }
+
+    /// Return the delay between two steps.
+    ///
+    /// When slowing down at branches, the delay is multiplied by [`BRANCH_SLOWDOWN`] while the
+    /// running interpreter is on `_`, `|`, `?` or `w`.
+    ///
+    /// [`BRANCH_SLOWDOWN`]: #associatedconstant.BRANCH_SLOWDOWN
+    pub fn delay(&self) -> Duration {
+        if self.at_branch {
+            Duration::from_millis(self.delay * Self::BRANCH_SLOWDOWN)
+        } else {
+            Duration::from_millis(self.delay)
+        }
+    }
+
+    /// Return whether the runtime slows down at branch instructions.
+    pub fn slow_branches(&self) -> bool {
+        self.slow_branches
+    }
+
+    /// Return the outcome of the last single step, if any.
+    pub fn last_step(&self) -> Option<StepOutcome> {
+        self.last_step
+    }
+
+    /// Return the error of the last failed step, if any.
+    pub fn error(&self) -> Option<StepError> {
+        self.error
+    }
+
+    /// Return whether the interpreter is running.
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Return whether the interpreter is fast-forwarding to the end of the program.
+    pub fn fast_forward(&self) -> bool {
+        self.fast_forward
+    }
+
+    /// Apply the given command to the runtime state and the interpreter.
+    pub fn handle(&mut self, cmd: RuntimeCommand, interpreter: &mut DebugInterpreter) {
+        match cmd {
+            RuntimeCommand::TogglePause if self.fast_forward => self.fast_forward = false,
+            RuntimeCommand::TogglePause => self.running = !self.running,
+            RuntimeCommand::Pause => {
+                self.running = false;
+                self.fast_forward = false;
+            }
+            RuntimeCommand::ToggleSlowBranches => self.slow_branches = !self.slow_branches,
+            RuntimeCommand::Slower => {
+                self.delay = cmp::min(self.delay + (self.delay / 5), Self::MAX_DELAY)
+            }
+            RuntimeCommand::Faster => {
+                self.delay = cmp::max(self.delay - (self.delay / 5), Self::MIN_DELAY)
+            }
+            RuntimeCommand::SetRate(rate) => {
+                self.delay =
+                    (1000 / u64::from(cmp::max(rate, 1))).clamp(Self::MIN_DELAY, Self::MAX_DELAY)
+            }
+            RuntimeCommand::RunToEnd => self.fast_forward = true,
+            RuntimeCommand::Step if !self.running => self.last_step = self.step(interpreter),
+            RuntimeCommand::StepBack if !self.running => {
+                interpreter.rewind(1);
+            }
+            _ => (),
+        }
+    }
+
+    /// Execute a step of the interpreter if it is running.
+    ///
+    /// When fast-forwarding, up to [`FAST_FORWARD_STEPS`] steps are executed instead. Fast-forwarding
+    /// ends and the runtime pauses once the program terminates or hits a breakpoint.
+    ///
+    /// [`FAST_FORWARD_STEPS`]: #associatedconstant.FAST_FORWARD_STEPS
+    pub fn tick(&mut self, interpreter: &mut DebugInterpreter) {
+        if self.fast_forward {
+            for _ in 0..Self::FAST_FORWARD_STEPS {
+                if let Some(StepOutcome::Continue) = self.step(interpreter) {
+                    continue;
+                }
+
+                self.fast_forward = false;
+                self.running = false;
+
+                break;
+            }
+        } else if self.running {
+            self.step(interpreter);
+        }
+
+        self.at_branch = self.running && self.slow_branches && Self::is_branch(interpreter);
+    }
+
+    /// Execute a single step, pausing the runtime if it fails.
+    ///
+    /// The failing instruction is not executed, so the cursor stays on the offending cell.
+    fn step(&mut self, interpreter: &mut DebugInterpreter) -> Option<StepOutcome> {
+        match interpreter.try_step() {
+            Ok(outcome) => {
+                self.error = None;
+
+                Some(outcome)
+            }
+            Err(err) => {
+                self.error = Some(err);
+                self.running = false;
+                self.fast_forward = false;
+
+                None
+            }
+        }
+    }
+
+    fn is_branch(interpreter: &DebugInterpreter) -> bool {
+        let c = interpreter.resolve(interpreter.field()[interpreter.nav().pos()]);
+
+        interpreter.mode() == Mode::Execute
+            && matches!(
+                c,
+                instr::HORIZONTAL_IF | instr::VERTICAL_IF | instr::RANDOM | instr::COMPARE
+            )
+    }
+}
+
+impl Default for RuntimeState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// The runtime environment for an interpreter instance
@@ -107,29 +290,18 @@ impl Runtime {
             let interpreter = interpreter.clone();
 
             thread::spawn(move || {
-                let mut delay = 100;
-                let mut running = false;
+                let mut state = RuntimeState::new();
 
                 loop {
                     let start = Instant::now();
 
                     for cmd in receiver.try_iter() {
-                        match cmd {
-                            RuntimeCommand::TogglePause => running = !running,
-                            RuntimeCommand::Slower => delay = cmp::min(delay + (delay / 5), 1000),
-                            RuntimeCommand::Faster => delay = cmp::max(delay - (delay / 5), 10),
-                            RuntimeCommand::Step if !running => {
-                                interpreter.lock().unwrap().next().unwrap_or(())
-                            }
-                            _ => (),
-                        }
+                        state.handle(cmd, &mut interpreter.lock().unwrap());
                     }
 
-                    if running {
-                        interpreter.lock().unwrap().next();
-                    }
+                    state.tick(&mut interpreter.lock().unwrap());
 
-                    if let Some(d) = Duration::from_millis(delay).checked_sub(start.elapsed()) {
+                    if let Some(d) = state.delay().checked_sub(start.elapsed()) {
                         thread::sleep(d);
                     }
                 }
@@ -145,15 +317,458 @@ impl Runtime {
     }
 }
 
+/// A color theme for the playfield
+///
+/// Each kind of instruction is mapped to a foreground color. The current position is
+/// highlighted with its own foreground and background colors.
+///
+/// For the heatmap, execution counts are mapped onto a gradient of colors from cold to hot.
+pub struct Theme {
+    cursor_fg: Color,
+    cursor_bg: Color,
+    colors: HashMap<InstructionKind, Color>,
+    heat: Vec<Color>,
+}
+
+impl Theme {
+    /// Create a theme with the given cursor colors and no instruction colors.
+    ///
+    /// The heatmap uses a gradient from dark gray to yellow.
+    pub fn new(cursor_fg: Color, cursor_bg: Color) -> Self {
+        Self {
+            cursor_fg,
+            cursor_bg,
+            colors: HashMap::new(),
+            heat: vec![
+                Color::DarkGray,
+                Color::Gray,
+                Color::White,
+                Color::Yellow,
+                Color::LightYellow,
+            ],
+        }
+    }
+
+    /// Create a theme with bright colors on the default background.
+    pub fn high_contrast() -> Self {
+        let mut theme = Self::new(Color::Black, Color::White);
+
+        theme.set_color(InstructionKind::Number, Color::LightCyan);
+        theme.set_color(InstructionKind::Operator, Color::LightYellow);
+        theme.set_color(InstructionKind::Movement, Color::LightGreen);
+        theme.set_color(InstructionKind::Branch, Color::LightMagenta);
+        theme.set_color(InstructionKind::Terminate, Color::LightRed);
+        theme.set_color(InstructionKind::Storage, Color::LightYellow);
+        theme.set_color(InstructionKind::Illegal, Color::White);
+
+        theme
+    }
+
+    /// Create a theme which avoids distinguishing instructions by red and green.
+    pub fn colorblind() -> Self {
+        let mut theme = Self::new(Color::Black, Color::Yellow);
+
+        theme.set_color(InstructionKind::Number, Color::Blue);
+        theme.set_color(InstructionKind::Operator, Color::Yellow);
+        theme.set_color(InstructionKind::Movement, Color::Cyan);
+        theme.set_color(InstructionKind::Branch, Color::Magenta);
+        theme.set_color(InstructionKind::Terminate, Color::Magenta);
+        theme.set_color(InstructionKind::Storage, Color::Yellow);
+
+        theme
+    }
+
+    /// Look up a predefined theme by its name.
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "colorblind" => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+
+    /// Set the color for the given kind of instructions.
+    pub fn set_color(&mut self, kind: InstructionKind, color: Color) {
+        self.colors.insert(kind, color);
+    }
+
+    /// Return the color for the given kind of instructions.
+    pub fn color(&self, kind: InstructionKind) -> Color {
+        self.colors.get(&kind).cloned().unwrap_or(Color::Reset)
+    }
+
+    /// Return the style for the given byte of the playfield.
+    pub fn style(&self, c: u8) -> Style {
+        Style::default().fg(self.color(InstructionKind::of(c)))
+    }
+
+    /// Set the gradient of the heatmap, starting with the color for cells which have never been
+    /// executed.
+    pub fn set_heat_colors(&mut self, heat: Vec<Color>) {
+        self.heat = heat;
+    }
+
+    /// Return the heatmap color for a cell executed `count` times out of at most `max` times.
+    ///
+    /// Counts are scaled logarithmically, so cells executed a few times can still be told
+    /// apart from cells which have never been executed when others run millions of times.
+    /// Only unexecuted cells get the coldest color and the hottest cells get the last color.
+    pub fn heat_color(&self, count: u32, max: u32) -> Color {
+        let levels = self.heat.len();
+
+        if levels == 0 {
+            return Color::Reset;
+        }
+
+        if count == 0 || levels == 1 {
+            return self.heat[0];
+        }
+
+        let scale = (f64::from(count) + 1.0).ln() / (f64::from(max.max(count)) + 1.0).ln();
+        let level = 1 + (scale * (levels - 2) as f64).round() as usize;
+
+        self.heat[level.min(levels - 1)]
+    }
+
+    /// Return the style for the current position.
+    pub fn cursor_style(&self) -> Style {
+        Style::default().bg(self.cursor_bg).fg(self.cursor_fg)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let mut theme = Self::new(Color::White, Color::Red);
+
+        theme.set_color(InstructionKind::Number, Color::Blue);
+        theme.set_color(InstructionKind::Operator, Color::Red);
+        theme.set_color(InstructionKind::Movement, Color::Red);
+        theme.set_color(InstructionKind::Branch, Color::Red);
+        theme.set_color(InstructionKind::Terminate, Color::Red);
+        theme.set_color(InstructionKind::Storage, Color::Red);
+
+        theme
+    }
+}
+
+/// An action of the user interface which can be bound to a key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    Step,
+    StepBack,
+    Slower,
+    Faster,
+    SetRate,
+    RunToEnd,
+    ToggleHeatmap,
+    NextProgram,
+    ToggleSlowBranches,
+    ToggleStackLayout,
+    Help,
+}
+
+impl Action {
+    /// All actions in the order they are listed in the help panel
+    pub const ALL: [Action; 13] = [
+        Action::Quit,
+        Action::TogglePause,
+        Action::Step,
+        Action::StepBack,
+        Action::Slower,
+        Action::Faster,
+        Action::SetRate,
+        Action::RunToEnd,
+        Action::ToggleHeatmap,
+        Action::NextProgram,
+        Action::ToggleSlowBranches,
+        Action::ToggleStackLayout,
+        Action::Help,
+    ];
+
+    /// Return the description of this action shown in the help panel.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::TogglePause => "pause or resume",
+            Action::Step => "execute a single step",
+            Action::StepBack => "undo a single step",
+            Action::Slower => "run slower",
+            Action::Faster => "run faster",
+            Action::SetRate => "set steps per second",
+            Action::RunToEnd => "run to the end",
+            Action::ToggleHeatmap => "toggle the execution heatmap",
+            Action::NextProgram => "switch to the next program",
+            Action::ToggleSlowBranches => "toggle slowing down at branches",
+            Action::ToggleStackLayout => "toggle the stack orientation",
+            Action::Help => "show this help",
+        }
+    }
+
+    /// Return the name of this action in key map files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::TogglePause => "toggle-pause",
+            Action::Step => "step",
+            Action::StepBack => "step-back",
+            Action::Slower => "slower",
+            Action::Faster => "faster",
+            Action::SetRate => "set-rate",
+            Action::RunToEnd => "run-to-end",
+            Action::ToggleHeatmap => "toggle-heatmap",
+            Action::NextProgram => "next-program",
+            Action::ToggleSlowBranches => "toggle-slow-branches",
+            Action::ToggleStackLayout => "toggle-stack-layout",
+            Action::Help => "help",
+        }
+    }
+
+    /// Look up an action by its name, see [`name`].
+    ///
+    /// [`name`]: #method.name
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    /// Return the command this action sends to the runtime of the active program.
+    ///
+    /// Returns `None` for actions which only affect the user interface.
+    pub fn command(self) -> Option<RuntimeCommand> {
+        match self {
+            Action::TogglePause => Some(RuntimeCommand::TogglePause),
+            Action::Step => Some(RuntimeCommand::Step),
+            Action::StepBack => Some(RuntimeCommand::StepBack),
+            Action::Slower => Some(RuntimeCommand::Slower),
+            Action::Faster => Some(RuntimeCommand::Faster),
+            Action::RunToEnd => Some(RuntimeCommand::RunToEnd),
+            Action::ToggleSlowBranches => Some(RuntimeCommand::ToggleSlowBranches),
+            _ => None,
+        }
+    }
+}
+
+/// A mapping of keys to actions
+///
+/// Every key triggers at most one action, but an action may be bound to several keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl KeyMap {
+    /// Create a key map without any bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Bind the given key to the given action, replacing any previous binding of the key.
+    pub fn bind(&mut self, key: Key, action: Action) {
+        self.bindings.retain(|&(k, _)| k != key);
+        self.bindings.push((key, action));
+    }
+
+    /// Remove all bindings of the given action.
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|&(_, a)| a != action);
+    }
+
+    /// Return the action bound to the given key, if any.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, action)| action)
+    }
+
+    /// Return all keys bound to the given action in the order they were bound.
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|&(key, _)| key)
+            .collect()
+    }
+
+    /// Return a short name of the given key for the help panel.
+    pub fn key_name(key: Key) -> String {
+        match key {
+            Key::Char('\t') => "Tab".to_string(),
+            Key::Char(' ') => "Space".to_string(),
+            Key::Char(c) => c.to_string(),
+            Key::Ctrl(c) => format!("Ctrl-{}", c),
+            Key::Alt(c) => format!("Alt-{}", c),
+            Key::F(n) => format!("F{}", n),
+            key => format!("{:?}", key),
+        }
+    }
+
+    /// Parse a key from its name as returned by [`key_name`].
+    ///
+    /// [`key_name`]: #method.key_name
+    pub fn parse_key(name: &str) -> Option<Key> {
+        let single = |s: &str| {
+            let mut chars = s.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        };
+
+        match name {
+            "Tab" => Some(Key::Char('\t')),
+            "Space" => Some(Key::Char(' ')),
+            "Backspace" => Some(Key::Backspace),
+            "Left" => Some(Key::Left),
+            "Right" => Some(Key::Right),
+            "Up" => Some(Key::Up),
+            "Down" => Some(Key::Down),
+            "Home" => Some(Key::Home),
+            "End" => Some(Key::End),
+            "PageUp" => Some(Key::PageUp),
+            "PageDown" => Some(Key::PageDown),
+            "Delete" => Some(Key::Delete),
+            "Insert" => Some(Key::Insert),
+            "Esc" => Some(Key::Esc),
+            _ => {
+                if let Some(c) = name.strip_prefix("Ctrl-").and_then(single) {
+                    Some(Key::Ctrl(c))
+                } else if let Some(c) = name.strip_prefix("Alt-").and_then(single) {
+                    Some(Key::Alt(c))
+                } else if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                    Some(Key::F(n))
+                } else {
+                    single(name).map(Key::Char)
+                }
+            }
+        }
+    }
+
+    /// Parse a key map from lines of the form `action = key...`, e.g. `step = Space n`.
+    ///
+    /// Actions which are listed lose their default keys, all others keep them. Keys are
+    /// separated by whitespace and named like in the help panel. Empty lines and lines starting
+    /// with `#` are ignored. Returns an error message naming the line of the first error.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut keys = Self::default();
+        let mut listed = Vec::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, names) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected action = key...", i + 1))?;
+            let action = Action::by_name(name.trim())
+                .ok_or_else(|| format!("line {}: unknown action {}", i + 1, name.trim()))?;
+
+            if !listed.contains(&action) {
+                keys.unbind(action);
+                listed.push(action);
+            }
+
+            for name in names.split_whitespace() {
+                let key = Self::parse_key(name)
+                    .ok_or_else(|| format!("line {}: unknown key {}", i + 1, name))?;
+
+                keys.bind(key, action);
+            }
+        }
+
+        Ok(keys)
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keys = Self::new();
+
+        keys.bind(Key::Char('q'), Action::Quit);
+        keys.bind(Key::Char('p'), Action::TogglePause);
+        keys.bind(Key::Char('n'), Action::Step);
+        keys.bind(Key::Char('b'), Action::StepBack);
+        keys.bind(Key::Left, Action::Slower);
+        keys.bind(Key::Right, Action::Faster);
+        keys.bind(Key::Char('s'), Action::SetRate);
+        keys.bind(Key::Char('e'), Action::RunToEnd);
+        keys.bind(Key::Char('m'), Action::ToggleHeatmap);
+        keys.bind(Key::Char('\t'), Action::NextProgram);
+        keys.bind(Key::Char('d'), Action::ToggleSlowBranches);
+        keys.bind(Key::Char('v'), Action::ToggleStackLayout);
+        keys.bind(Key::Char('?'), Action::Help);
+        keys.bind(Key::Char('h'), Action::Help);
+
+        keys
+    }
+}
+
+/// A widget which clears its area
+///
+/// This is used to render widgets on top of other widgets.
+pub struct Clear;
+
+impl Widget for Clear {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).reset();
+            }
+        }
+    }
+}
+
+/// The orientation of the stack panel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StackLayout {
+    /// All values on one wrapped line, bottom first
+    Horizontal,
+    /// One value per line, top of the stack on the first line
+    TopFirst,
+    /// One value per line, top of the stack on the last line
+    BottomFirst,
+}
+
+impl StackLayout {
+    /// Return the layout following this one when toggling.
+    pub fn next(self) -> Self {
+        match self {
+            StackLayout::Horizontal => StackLayout::TopFirst,
+            StackLayout::TopFirst => StackLayout::BottomFirst,
+            StackLayout::BottomFirst => StackLayout::Horizontal,
+        }
+    }
+}
+
 /// User interface to render the interpreter
 pub struct UserInterface {
     terminal: Terminal<TermionBackend<AlternateScreen<RawTerminal<io::Stdout>>>>,
-    interpreter: Arc<Mutex<DebugInterpreter>>,
+    interpreters: Vec<Arc<Mutex<DebugInterpreter>>>,
+    current: usize,
+    theme: Theme,
+    help: bool,
+    prompt: Option<String>,
+    heatmap: bool,
+    stack_layout: StackLayout,
+    keys: KeyMap,
 }
 
 impl UserInterface {
-    /// Create a new UI for the given interpreter.
-    pub fn new(interpreter: Arc<Mutex<DebugInterpreter>>) -> io::Result<Self> {
+    /// Create a new UI for the given interpreters.
+    ///
+    /// Only the active interpreter is shown, starting with the first one.
+    pub fn new(interpreters: Vec<Arc<Mutex<DebugInterpreter>>>, theme: Theme) -> io::Result<Self> {
         let stdout = io::stdout().into_raw_mode()?;
         let backend = TermionBackend::new(AlternateScreen::from(stdout));
         let mut terminal = Terminal::new(backend)?;
@@ -162,21 +777,146 @@ impl UserInterface {
 
         Ok(Self {
             terminal,
-            interpreter,
+            interpreters,
+            current: 0,
+            theme,
+            help: false,
+            prompt: None,
+            heatmap: false,
+            stack_layout: StackLayout::Horizontal,
+            keys: KeyMap::default(),
         })
     }
 
+    /// Return the index of the active interpreter.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Switch to the next interpreter, wrapping around after the last one.
+    pub fn next_interpreter(&mut self) {
+        self.current = (self.current + 1) % self.interpreters.len()
+    }
+
+    /// Return the key bindings.
+    pub fn key_map(&self) -> &KeyMap {
+        &self.keys
+    }
+
+    /// Replace the key bindings, which are also listed in the help panel.
+    pub fn set_key_map(&mut self, keys: KeyMap) {
+        self.keys = keys
+    }
+
+    /// Return whether the help panel is shown.
+    pub fn help(&self) -> bool {
+        self.help
+    }
+
+    /// Show or hide the help panel.
+    pub fn set_help(&mut self, help: bool) {
+        self.help = help
+    }
+
+    /// Show or hide the execution heatmap instead of the instruction colors.
+    pub fn toggle_heatmap(&mut self) {
+        self.heatmap = !self.heatmap
+    }
+
+    /// Switch to the next orientation of the stack panel.
+    pub fn toggle_stack_layout(&mut self) {
+        self.stack_layout = self.stack_layout.next()
+    }
+
+    /// Return the text entered into the rate prompt, if it is shown.
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    /// Show or hide the rate prompt, starting with empty text.
+    pub fn set_prompt(&mut self, prompt: bool) {
+        self.prompt = if prompt { Some(String::new()) } else { None }
+    }
+
+    /// Handle a key press while the rate prompt is shown.
+    ///
+    /// Returns the entered rate once the prompt is confirmed with enter.
+    pub fn prompt_key(&mut self, k: Key) -> Option<u32> {
+        let prompt = self.prompt.as_mut()?;
+
+        match k {
+            Key::Char('\n') => {
+                let rate = prompt.parse().ok();
+
+                self.prompt = None;
+
+                rate
+            }
+            Key::Char(c) if c.is_ascii_digit() => {
+                prompt.push(c);
+
+                None
+            }
+            Key::Backspace => {
+                prompt.pop();
+
+                None
+            }
+            Key::Esc => {
+                self.prompt = None;
+
+                None
+            }
+            _ => None,
+        }
+    }
+
     /// Render the current state of the interpreter.
     pub fn render(&mut self) -> io::Result<()> {
-        let interpreter = self.interpreter.lock().unwrap();
+        let interpreter = self.interpreters[self.current].lock().unwrap();
 
         let width = interpreter.field().width();
         let height = interpreter.field().height();
 
-        let playfield = Self::format_playfield(interpreter.field(), interpreter.nav().pos());
-        let stack = Self::format_stack(interpreter.stack());
+        let heat = if self.heatmap {
+            Some(Self::execution_counts(&interpreter))
+        } else {
+            None
+        };
+        let playfield = Self::format_playfield(
+            interpreter.field(),
+            interpreter.nav().pos(),
+            &self.theme,
+            heat.as_deref(),
+        );
+        let title = Self::playfield_title(
+            interpreter.predict_branch(),
+            self.current,
+            self.interpreters.len(),
+        );
+        let stack = Self::format_stack(
+            interpreter.stack(),
+            interpreter.stack_as_string(),
+            self.stack_layout,
+        );
+        let effect = interpreter.last_effect();
+        let output_title = Self::effect_title(" Output ", effect == StepEffect::OutputWritten);
+        let input_title = Self::effect_title(
+            " Input ",
+            matches!(effect, StepEffect::InputRead | StepEffect::InputDefaulted),
+        );
+        let title = Self::effect_title(&title, effect == StepEffect::FieldModified);
         let output = Self::format_output(interpreter.io().writer());
         let input = Self::format_input(interpreter.io().reader().get_ref());
+        let help = if self.help {
+            Some(Self::format_help(&self.theme, &self.keys))
+        } else {
+            None
+        };
+        let prompt = self
+            .prompt
+            .as_ref()
+            .map(|p| [Text::raw(format!("Steps per second: {}", p))]);
 
         self.terminal.draw(|mut f| {
             // -- define layout
@@ -204,7 +944,7 @@ impl UserInterface {
 
             // -- render blocks and paragraphs
             Paragraph::new(playfield.iter())
-                .block(Block::default().title(" Playfield ").borders(Borders::ALL))
+                .block(Block::default().title(&title).borders(Borders::ALL))
                 .alignment(Alignment::Center)
                 .render(&mut f, left[0]);
 
@@ -215,18 +955,119 @@ impl UserInterface {
                 .render(&mut f, left[1]);
 
             Paragraph::new(output.iter())
-                .block(Block::default().title(" Output ").borders(Borders::ALL))
+                .block(Block::default().title(&output_title).borders(Borders::ALL))
                 .alignment(Alignment::Left)
                 .render(&mut f, right[0]);
 
             Paragraph::new(input.iter())
-                .block(Block::default().title(" Input ").borders(Borders::ALL))
+                .block(Block::default().title(&input_title).borders(Borders::ALL))
                 .alignment(Alignment::Left)
                 .render(&mut f, right[1]);
+
+            // -- render the help panel on top
+            if let Some(help) = help {
+                let lines: usize = help
+                    .iter()
+                    .map(|t| match t {
+                        Text::Raw(d) | Text::Styled(d, _) => d.matches('\n').count(),
+                    })
+                    .sum();
+                let area = Self::centered(f.size(), 50, lines as u16 + 3);
+
+                Clear.render(&mut f, area);
+
+                Paragraph::new(help.iter())
+                    .block(Block::default().title(" Help ").borders(Borders::ALL))
+                    .alignment(Alignment::Left)
+                    .render(&mut f, area);
+            }
+
+            // -- render the rate prompt on top
+            if let Some(prompt) = prompt {
+                let area = Self::centered(f.size(), 40, 3);
+
+                Clear.render(&mut f, area);
+
+                Paragraph::new(prompt.iter())
+                    .block(Block::default().title(" Rate ").borders(Borders::ALL))
+                    .alignment(Alignment::Left)
+                    .render(&mut f, area);
+            }
         })
     }
 
-    fn format_playfield(playfield: &Playfield, pos: (usize, usize)) -> Vec<Text> {
+    fn centered(area: Rect, width: u16, height: u16) -> Rect {
+        let width = cmp::min(width, area.width);
+        let height = cmp::min(height, area.height);
+
+        Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    fn format_help(theme: &Theme, keys: &KeyMap) -> Vec<Text<'static>> {
+        let mut text = vec![Text::raw("Keys\n")];
+
+        for &action in Action::ALL.iter() {
+            let names: Vec<String> = keys
+                .keys(action)
+                .into_iter()
+                .map(KeyMap::key_name)
+                .collect();
+
+            if !names.is_empty() {
+                let names = names.join(" / ");
+
+                text.push(Text::raw(format!(
+                    "  {:<8}{}\n",
+                    names,
+                    action.description()
+                )));
+            }
+        }
+
+        text.push(Text::raw("\nInstructions\n"));
+
+        let mut kinds: Vec<(InstructionKind, String)> = Vec::new();
+
+        for info in instruction_set(Dialect::Befunge93) {
+            match kinds.iter_mut().find(|(kind, _)| *kind == info.kind) {
+                Some((_, bytes)) => bytes.push(info.byte as char),
+                None => kinds.push((info.kind, (info.byte as char).to_string())),
+            }
+        }
+
+        for (kind, bytes) in kinds {
+            text.push(Text::raw(format!("  {:<12}", format!("{:?}", kind))));
+            text.push(Text::styled(bytes, Style::default().fg(theme.color(kind))));
+            text.push(Text::raw("\n"));
+        }
+
+        text.push(Text::raw("\nPress any key to close"));
+        text
+    }
+
+    fn execution_counts(interpreter: &DebugInterpreter) -> Vec<u32> {
+        let (width, height) = interpreter.field().dimensions();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| interpreter.execution_count(x, y))
+            .collect()
+    }
+
+    fn format_playfield<'a>(
+        playfield: &'a Playfield,
+        pos: (usize, usize),
+        theme: &'a Theme,
+        heat: Option<&'a [u32]>,
+    ) -> Vec<Text<'a>> {
+        let width = playfield.width();
+        let max = heat.and_then(|h| h.iter().max().cloned()).unwrap_or(0);
+
         playfield
             .lines()
             .enumerate()
@@ -234,29 +1075,14 @@ impl UserInterface {
                 l.chunks(1)
                     .enumerate()
                     .map(move |(x, c)| {
-                        let data = str::from_utf8(c).unwrap();
-
-                        let style = match c[0] {
-                            // current position
-                            _ if pos == (x, y) => Style::default().bg(Color::Red).fg(Color::White),
-                            // numbers
-                            b'0'...b'9' => Style::default().fg(Color::Blue),
-                            // operators
-                            b'+' | b'-' | b'*' | b'/' | b'%' | b'!' | b'`' => {
-                                Style::default().fg(Color::Red)
+                        let data = Self::format_cell(c[0]);
+
+                        let style = match heat {
+                            _ if pos == (x, y) => theme.cursor_style(),
+                            Some(heat) => {
+                                Style::default().fg(theme.heat_color(heat[x + width * y], max))
                             }
-                            // movement
-                            b'>' | b'<' | b'^' | b'v' | b'?' => Style::default().fg(Color::Red),
-                            // branching
-                            b'_' | b'|' | b'#' | b'@' => Style::default().fg(Color::Red),
-                            // stack
-                            b':' | b'\\' | b'$' | b'"' => Style::default(),
-                            // io
-                            b'.' | b',' | b'&' | b'~' => Style::default(),
-                            // storage
-                            b'p' | b'g' => Style::default().fg(Color::Red),
-                            // others
-                            _ => Style::default(),
+                            None => theme.style(c[0]),
                         };
 
                         Text::styled(data, style)
@@ -266,64 +1092,568 @@ impl UserInterface {
             .collect()
     }
 
-    fn format_stack(stack: &Stack) -> [Text; 1] {
-        [Text::raw(format!("{:?}", stack))]
+    fn format_cell(c: u8) -> Cow<'static, str> {
+        match c {
+            // printable ASCII characters
+            0x20..=0x7e => Cow::from((c as char).to_string()),
+            // placeholder for everything else
+            _ => Cow::from("·"),
+        }
     }
 
-    fn format_output(output: &[u8]) -> [Text; 1] {
-        [Text::raw(str::from_utf8(output).unwrap())]
+    /// Mark the title of a panel affected by the last step.
+    fn effect_title(title: &str, affected: bool) -> String {
+        if affected {
+            format!("{}* ", title)
+        } else {
+            title.to_string()
+        }
+    }
+
+    fn playfield_title(branch: Option<playfield::Direction>, current: usize, n: usize) -> String {
+        let mut title = String::from(" Playfield ");
+
+        if n > 1 {
+            title.push_str(&format!("{}/{} ", current + 1, n));
+        }
+
+        if let Some(dir) = branch {
+            title.push_str(&format!("(next: {:?}) ", dir));
+        }
+
+        title
+    }
+
+    fn format_stack(stack: &Stack, string: String, layout: StackLayout) -> [Text<'_>; 2] {
+        let values = match layout {
+            StackLayout::Horizontal => interpreter::format_stack(stack),
+            StackLayout::TopFirst => interpreter::format_stack_vertical(stack, true),
+            StackLayout::BottomFirst => interpreter::format_stack_vertical(stack, false),
+        };
+
+        [
+            Text::raw(values),
+            Text::raw(format!("\n\nAs string: {:?}", string)),
+        ]
+    }
+
+    fn format_output(output: &[u8]) -> [Text<'_>; 1] {
+        [Text::raw(String::from_utf8_lossy(output))]
+    }
+
+    fn format_input(input: &[u8]) -> [Text<'_>; 1] {
+        [Text::raw(String::from_utf8_lossy(input))]
+    }
+}
+
+/// The command line options
+#[derive(Debug, PartialEq)]
+struct Options<'a> {
+    theme: &'a str,
+    keys: Option<&'a str>,
+    max_size: Option<u64>,
+    files: &'a [String],
+}
+
+/// Parse the options from the given command line arguments.
+///
+/// Returns `None` if the arguments are invalid or no file is given.
+fn parse_args(mut args: &[String]) -> Option<Options<'_>> {
+    let mut options = Options {
+        theme: "default",
+        keys: None,
+        max_size: None,
+        files: &[],
+    };
+
+    loop {
+        match args {
+            [flag, name, rest @ ..] if flag == "--theme" => {
+                options.theme = name;
+                args = rest;
+            }
+            [flag, file, rest @ ..] if flag == "--keys" => {
+                options.keys = Some(file);
+                args = rest;
+            }
+            [flag, size, rest @ ..] if flag == "--max-size" => {
+                options.max_size = Some(size.parse().ok()?);
+                args = rest;
+            }
+            files => {
+                options.files = files;
+                break;
+            }
+        }
     }
 
-    fn format_input(input: &[u8]) -> [Text; 1] {
-        [Text::raw(str::from_utf8(input).unwrap())]
+    if options.files.is_empty() {
+        None
+    } else {
+        Some(options)
     }
 }
 
 fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        println!("Usage: ./defunge <file>");
+    let options = parse_args(&args[1..]);
+    let theme = options
+        .as_ref()
+        .and_then(|options| Theme::by_name(options.theme));
 
-        process::exit(1);
-    }
+    let (options, theme) = match (options, theme) {
+        (Some(options), Some(theme)) => (options, theme),
+        _ => {
+            println!(
+                "Usage: ./defunge [--theme default|high-contrast|colorblind] [--keys <file>] [--max-size <bytes>] <file>..."
+            );
 
-    // obtain the interpreter
-    let mut file = File::open(&args[1])?;
-    let mut contents = String::new();
+            process::exit(1);
+        }
+    };
 
-    file.read_to_string(&mut contents)?;
+    let keys = match options.keys {
+        Some(file) => {
+            let source = fs::read_to_string(file).unwrap_or_else(|e| {
+                eprintln!("could not open {}: {}", file, e);
 
-    let playfield = Playfield::new(&contents);
-    let input = Cursor::new(Vec::new());
-    let output = Vec::new();
-    let io = InputOutput::new(input, output);
+                process::exit(1);
+            });
 
-    let interpreter = Interpreter::new(playfield, io);
-    let arc = Arc::new(Mutex::new(interpreter));
+            KeyMap::parse(&source).unwrap_or_else(|e| {
+                eprintln!("{}: {}", file, e);
 
-    // start the event queue and the runtime environment
+                process::exit(1);
+            })
+        }
+        None => KeyMap::default(),
+    };
+
+    // obtain one interpreter per file
+    let interpreters: Vec<_> = options
+        .files
+        .iter()
+        .map(|file| {
+            let playfield =
+                Playfield::from_path_with_limit(file, options.max_size).unwrap_or_else(|e| {
+                    eprintln!("{}", e.describe(file));
+
+                    process::exit(1);
+                });
+            let input = Cursor::new(Vec::new());
+            let output = Vec::new();
+            let io = InputOutput::new(input, output);
+
+            let mut interpreter = Interpreter::new(playfield, io);
+            interpreter.set_history_depth(1000);
+            interpreter.set_coverage_tracking(true);
+
+            Arc::new(Mutex::new(interpreter))
+        })
+        .collect();
+
+    // start the event queue and one runtime environment per interpreter
     let events = Events::new(30);
-    let runtime = Runtime::new(Arc::clone(&arc));
+    let runtimes: Vec<_> = interpreters
+        .iter()
+        .map(|arc| Runtime::new(Arc::clone(arc)))
+        .collect();
 
     // prepare the terminal
-    let mut ui = UserInterface::new(arc)?;
+    let mut ui = UserInterface::new(interpreters, theme)?;
+
+    ui.set_key_map(keys);
 
     // start the rendering loop
     loop {
         ui.render()?;
 
         if let Event::Input(k) = events.next() {
-            match k {
-                Key::Char('q') => break,
-                Key::Char('p') => runtime.send(RuntimeCommand::TogglePause),
-                Key::Char('n') => runtime.send(RuntimeCommand::Step),
-                Key::Left => runtime.send(RuntimeCommand::Slower),
-                Key::Right => runtime.send(RuntimeCommand::Faster),
-                _ => (),
+            if ui.help() {
+                ui.set_help(false);
+
+                continue;
+            }
+
+            let runtime = &runtimes[ui.current()];
+
+            if ui.prompt().is_some() {
+                if let Some(rate) = ui.prompt_key(k) {
+                    runtime.send(RuntimeCommand::SetRate(rate));
+                }
+
+                continue;
+            }
+
+            match ui.key_map().action(k) {
+                Some(Action::Quit) => break,
+                Some(Action::Help) => ui.set_help(true),
+                Some(Action::SetRate) => ui.set_prompt(true),
+                Some(Action::ToggleHeatmap) => ui.toggle_heatmap(),
+                Some(Action::ToggleStackLayout) => ui.toggle_stack_layout(),
+                Some(Action::NextProgram) => {
+                    runtime.send(RuntimeCommand::Pause);
+                    ui.next_interpreter();
+                }
+                Some(action) => {
+                    if let Some(cmd) = action.command() {
+                        runtime.send(cmd);
+                    }
+                }
+                None => (),
             }
         }
     }
 
+    // The runtime threads are detached, so returning from main ends the process and stops all
+    // runtimes at once, including those of the inactive interpreters.
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_map_remap() {
+        let mut keys = KeyMap::default();
+
+        assert_eq!(Some(Action::Step), keys.action(Key::Char('n')));
+        assert_eq!(Some(RuntimeCommand::Step), Action::Step.command());
+
+        keys.unbind(Action::Step);
+        keys.bind(Key::Char(' '), Action::Step);
+        keys.bind(Key::Char('p'), Action::Faster);
+
+        assert_eq!(None, keys.action(Key::Char('n')));
+        assert_eq!(
+            Some(RuntimeCommand::Step),
+            keys.action(Key::Char(' ')).and_then(Action::command)
+        );
+        assert_eq!(
+            Some(RuntimeCommand::Faster),
+            keys.action(Key::Char('p')).and_then(Action::command)
+        );
+        assert!(keys.keys(Action::TogglePause).is_empty());
+        assert_eq!(None, Action::Quit.command());
+
+        let help = UserInterface::format_help(&Theme::default(), &keys);
+
+        assert!(help.iter().any(|t| match t {
+            Text::Raw(d) => d == "  Space   execute a single step\n",
+            _ => false,
+        }));
+        assert!(!help.iter().any(|t| match t {
+            Text::Raw(d) => d.ends_with("pause or resume\n"),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn key_map_parse() {
+        let keys = KeyMap::parse("# my keys\n\nstep = Space n\n  faster = p Ctrl-f\n").unwrap();
+
+        assert_eq!(
+            vec![Key::Char(' '), Key::Char('n')],
+            keys.keys(Action::Step)
+        );
+        assert_eq!(
+            vec![Key::Char('p'), Key::Ctrl('f')],
+            keys.keys(Action::Faster)
+        );
+        assert!(keys.keys(Action::TogglePause).is_empty());
+        assert_eq!(vec![Key::Char('q')], keys.keys(Action::Quit));
+
+        let keys = KeyMap::parse("faster = PageUp\nfaster = F5").unwrap();
+
+        assert_eq!(vec![Key::PageUp, Key::F(5)], keys.keys(Action::Faster));
+        assert_eq!(
+            Some(RuntimeCommand::Faster),
+            keys.action(Key::F(5)).and_then(Action::command)
+        );
+
+        assert_eq!(
+            Err("line 2: unknown action jump".to_string()),
+            KeyMap::parse("step = n\njump = j")
+        );
+        assert_eq!(
+            Err("line 1: unknown key Hyper-x".to_string()),
+            KeyMap::parse("quit = Hyper-x")
+        );
+        assert_eq!(
+            Err("line 1: expected action = key...".to_string()),
+            KeyMap::parse("quit")
+        );
+    }
+
+    #[test]
+    fn key_names() {
+        let keys = KeyMap::default();
+
+        for &action in Action::ALL.iter() {
+            assert_eq!(Some(action), Action::by_name(action.name()));
+
+            for key in keys.keys(action) {
+                assert_eq!(Some(key), KeyMap::parse_key(&KeyMap::key_name(key)));
+            }
+        }
+
+        assert_eq!(Some(Key::Alt('x')), KeyMap::parse_key("Alt-x"));
+        assert_eq!(Some(Key::Char('F')), KeyMap::parse_key("F"));
+        assert_eq!(None, KeyMap::parse_key("Tabs"));
+    }
+
+    #[test]
+    fn help_legend() {
+        let help = UserInterface::format_help(&Theme::default(), &KeyMap::default());
+
+        assert!(help.iter().any(|t| match t {
+            Text::Raw(d) => d == "  q       quit\n",
+            _ => false,
+        }));
+        assert!(help.iter().any(|t| match t {
+            Text::Raw(d) => d == "  ? / h   show this help\n",
+            _ => false,
+        }));
+        assert!(help.iter().any(|t| match t {
+            Text::Styled(d, s) => d == "0123456789" && *s == Style::default().fg(Color::Blue),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn format_non_utf8() {
+        match &UserInterface::format_output(&[b'a', 0xc8, b'b'])[0] {
+            Text::Raw(d) => assert_eq!("a\u{fffd}b", d),
+            _ => panic!("unexpected styled text"),
+        }
+
+        assert_eq!("x", UserInterface::format_cell(b'x'));
+        assert_eq!("·", UserInterface::format_cell(0xc8));
+        assert_eq!("·", UserInterface::format_cell(b'\t'));
+    }
+
+    #[test]
+    fn stack_layout_cycle() {
+        let layout = StackLayout::Horizontal.next();
+
+        assert_eq!(StackLayout::TopFirst, layout);
+        assert_eq!(StackLayout::BottomFirst, layout.next());
+        assert_eq!(StackLayout::Horizontal, layout.next().next());
+    }
+
+    #[test]
+    fn effect_title_marker() {
+        assert_eq!(" Output ", UserInterface::effect_title(" Output ", false));
+        assert_eq!(" Output * ", UserInterface::effect_title(" Output ", true));
+    }
+
+    #[test]
+    fn playfield_title_branch() {
+        assert_eq!(" Playfield ", UserInterface::playfield_title(None, 0, 1));
+        assert_eq!(
+            " Playfield (next: Left) ",
+            UserInterface::playfield_title(Some(playfield::Direction::Left), 0, 1)
+        );
+        assert_eq!(
+            " Playfield 2/3 ",
+            UserInterface::playfield_title(None, 1, 3)
+        );
+    }
+
+    #[test]
+    fn parse_multiple_files() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(None, parse_args(&args(&[])));
+        assert_eq!(None, parse_args(&args(&["--theme", "colorblind"])));
+
+        let files = args(&["a.bf", "b.bf"]);
+        let options = parse_args(&files).unwrap();
+
+        assert_eq!("default", options.theme);
+        assert_eq!(None, options.keys);
+        assert_eq!(None, options.max_size);
+        assert_eq!(&files[..], options.files);
+
+        let with_options = args(&[
+            "--theme",
+            "colorblind",
+            "--keys",
+            "keys.conf",
+            "--max-size",
+            "100",
+            "a.bf",
+            "b.bf",
+        ]);
+        let options = parse_args(&with_options).unwrap();
+
+        assert_eq!("colorblind", options.theme);
+        assert_eq!(Some("keys.conf"), options.keys);
+        assert_eq!(Some(100), options.max_size);
+        assert_eq!(&files[..], options.files);
+        assert_eq!(None, parse_args(&args(&["--max-size", "x", "a.bf"])));
+    }
+
+    #[test]
+    fn runtime_set_rate() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("@"), io);
+        let mut state = RuntimeState::new();
+
+        state.handle(RuntimeCommand::SetRate(10), &mut interpreter);
+        assert_eq!(Duration::from_millis(100), state.delay());
+
+        state.handle(RuntimeCommand::SetRate(1000), &mut interpreter);
+        assert_eq!(Duration::from_millis(10), state.delay());
+
+        state.handle(RuntimeCommand::SetRate(0), &mut interpreter);
+        assert_eq!(Duration::from_millis(1000), state.delay());
+    }
+
+    #[test]
+    fn runtime_run_to_end() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("5@"), io);
+        let mut state = RuntimeState::new();
+
+        state.handle(RuntimeCommand::RunToEnd, &mut interpreter);
+        assert!(state.fast_forward());
+
+        state.tick(&mut interpreter);
+
+        assert!(!state.fast_forward());
+        assert!(!state.running());
+        assert_eq!(2, interpreter.steps());
+
+        state.tick(&mut interpreter);
+
+        assert_eq!(2, interpreter.steps());
+    }
+
+    #[test]
+    fn runtime_step() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("5.@"), io);
+        let mut state = RuntimeState::new();
+
+        assert_eq!(None, state.last_step());
+
+        state.handle(RuntimeCommand::Step, &mut interpreter);
+        state.handle(RuntimeCommand::Step, &mut interpreter);
+
+        assert_eq!(Some(StepOutcome::Continue), state.last_step());
+        assert_eq!(StepEffect::OutputWritten, interpreter.last_effect());
+
+        state.handle(RuntimeCommand::Step, &mut interpreter);
+        assert_eq!(Some(StepOutcome::Terminated), state.last_step());
+
+        let steps = interpreter.steps();
+
+        state.handle(RuntimeCommand::Step, &mut interpreter);
+
+        assert_eq!(Some(StepOutcome::Terminated), state.last_step());
+        assert_eq!(steps, interpreter.steps());
+        assert_eq!((2, 0), interpreter.nav().pos());
+        assert_eq!(b"5 ", &interpreter.io().writer()[..]);
+    }
+
+    #[test]
+    fn runtime_illegal() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("1x@"), io);
+        let mut state = RuntimeState::new();
+
+        state.handle(RuntimeCommand::TogglePause, &mut interpreter);
+        state.tick(&mut interpreter);
+        state.tick(&mut interpreter);
+
+        assert!(!state.running());
+        assert_eq!(
+            Some(StepError::IllegalInstruction {
+                pos: (1, 0),
+                instruction: b'x',
+            }),
+            state.error()
+        );
+        assert_eq!((1, 0), interpreter.nav().pos());
+
+        state.handle(RuntimeCommand::RunToEnd, &mut interpreter);
+        state.tick(&mut interpreter);
+
+        assert!(!state.fast_forward());
+        assert_eq!(1, interpreter.steps());
+    }
+
+    #[test]
+    fn runtime_pause() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("5@"), io);
+        let mut state = RuntimeState::new();
+
+        state.handle(RuntimeCommand::Pause, &mut interpreter);
+        assert!(!state.running());
+
+        state.handle(RuntimeCommand::TogglePause, &mut interpreter);
+        state.handle(RuntimeCommand::RunToEnd, &mut interpreter);
+        state.handle(RuntimeCommand::Pause, &mut interpreter);
+
+        assert!(!state.running());
+        assert!(!state.fast_forward());
+    }
+
+    #[test]
+    fn runtime_slow_branches() {
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("1_@"), io);
+        let mut state = RuntimeState::new();
+
+        state.handle(RuntimeCommand::TogglePause, &mut interpreter);
+        state.tick(&mut interpreter);
+
+        assert_eq!((1, 0), interpreter.nav().pos());
+        assert_eq!(Duration::from_millis(100), state.delay());
+
+        let io = InputOutput::new(Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("1_@"), io);
+
+        state.handle(RuntimeCommand::ToggleSlowBranches, &mut interpreter);
+        assert!(state.slow_branches());
+
+        state.tick(&mut interpreter);
+
+        assert_eq!((1, 0), interpreter.nav().pos());
+        assert_eq!(Duration::from_millis(500), state.delay());
+
+        state.tick(&mut interpreter);
+
+        assert_eq!(Duration::from_millis(100), state.delay());
+    }
+
+    #[test]
+    fn theme_heat_color() {
+        let theme = Theme::default();
+
+        assert_eq!(Color::DarkGray, theme.heat_color(0, 0));
+        assert_eq!(Color::DarkGray, theme.heat_color(0, 1000));
+        assert_eq!(Color::Gray, theme.heat_color(1, 1_000_000));
+        assert_eq!(Color::White, theme.heat_color(30, 1000));
+        assert_eq!(Color::LightYellow, theme.heat_color(1, 1));
+        assert_eq!(Color::LightYellow, theme.heat_color(1000, 1000));
+    }
+
+    #[test]
+    fn theme_style() {
+        let default = Theme::default();
+
+        assert_eq!(Style::default().fg(Color::Blue), default.style(b'5'));
+        assert_eq!(Style::default().fg(Color::Reset), default.style(b':'));
+
+        let mut custom = Theme::default();
+        custom.set_color(InstructionKind::Number, Color::Green);
+
+        assert_eq!(Style::default().fg(Color::Green), custom.style(b'5'));
+        assert_eq!(default.style(b'+'), custom.style(b'+'));
+    }
+}
@@ -0,0 +1,551 @@
+/// A dialect of the Befunge language
+///
+/// Befunge-93 is fully supported. The Funge-98 dialect adds a subset of the Funge-98
+/// instructions on top of Befunge-93, but Funge-space is still bounded by the playfield.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    #[default]
+    Befunge93,
+    Funge98,
+}
+
+/// Named constants for the bytes of all instructions
+///
+/// The names follow the names in the instruction tables.
+pub mod instr {
+    // Befunge-93
+    pub const PUSH_0: u8 = b'0';
+    pub const PUSH_1: u8 = b'1';
+    pub const PUSH_2: u8 = b'2';
+    pub const PUSH_3: u8 = b'3';
+    pub const PUSH_4: u8 = b'4';
+    pub const PUSH_5: u8 = b'5';
+    pub const PUSH_6: u8 = b'6';
+    pub const PUSH_7: u8 = b'7';
+    pub const PUSH_8: u8 = b'8';
+    pub const PUSH_9: u8 = b'9';
+    pub const ADD: u8 = b'+';
+    pub const SUBTRACT: u8 = b'-';
+    pub const MULTIPLY: u8 = b'*';
+    pub const DIVIDE: u8 = b'/';
+    pub const MODULO: u8 = b'%';
+    pub const NOT: u8 = b'!';
+    pub const GREATER: u8 = b'`';
+    pub const RIGHT: u8 = b'>';
+    pub const LEFT: u8 = b'<';
+    pub const UP: u8 = b'^';
+    pub const DOWN: u8 = b'v';
+    pub const RANDOM: u8 = b'?';
+    pub const HORIZONTAL_IF: u8 = b'_';
+    pub const VERTICAL_IF: u8 = b'|';
+    pub const BRIDGE: u8 = b'#';
+    pub const STRING_MODE: u8 = b'"';
+    pub const DUPLICATE: u8 = b':';
+    pub const SWAP: u8 = b'\\';
+    pub const DISCARD: u8 = b'$';
+    pub const OUTPUT_INTEGER: u8 = b'.';
+    pub const OUTPUT_CHARACTER: u8 = b',';
+    pub const INPUT_INTEGER: u8 = b'&';
+    pub const INPUT_CHARACTER: u8 = b'~';
+    pub const PUT: u8 = b'p';
+    pub const GET: u8 = b'g';
+    pub const TERMINATE: u8 = b'@';
+    pub const SPACE: u8 = b' ';
+
+    // Funge-98
+    pub const PUSH_10: u8 = b'a';
+    pub const PUSH_11: u8 = b'b';
+    pub const PUSH_12: u8 = b'c';
+    pub const PUSH_13: u8 = b'd';
+    pub const PUSH_14: u8 = b'e';
+    pub const PUSH_15: u8 = b'f';
+    pub const CLEAR: u8 = b'n';
+    pub const TURN_LEFT: u8 = b'[';
+    pub const TURN_RIGHT: u8 = b']';
+    pub const REFLECT: u8 = b'r';
+    pub const ABSOLUTE_DELTA: u8 = b'x';
+    pub const COMPARE: u8 = b'w';
+    pub const NO_OP: u8 = b'z';
+}
+
+/// The kind of an instruction
+///
+/// Instructions are grouped by their purpose, e.g. to highlight them in a user interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InstructionKind {
+    Number,
+    Operator,
+    Movement,
+    Branch,
+    Terminate,
+    Stack,
+    StringMode,
+    Io,
+    Storage,
+    NoOp,
+    Illegal,
+}
+
+impl InstructionKind {
+    /// Classify the given byte as a Befunge-93 instruction.
+    ///
+    /// Spaces are classified as [`NoOp`] and bytes which are no instructions as [`Illegal`].
+    ///
+    /// [`NoOp`]: #variant.NoOp
+    /// [`Illegal`]: #variant.Illegal
+    pub fn of(c: u8) -> Self {
+        match InstructionInfo::lookup(c, Dialect::Befunge93) {
+            Some(info) => info.kind,
+            None if c == instr::SPACE => InstructionKind::NoOp,
+            None => InstructionKind::Illegal,
+        }
+    }
+}
+
+/// The description of a single instruction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstructionInfo {
+    pub byte: u8,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: InstructionKind,
+}
+
+impl InstructionInfo {
+    /// Look up the instruction for the given byte in the given dialect.
+    pub fn lookup(c: u8, dialect: Dialect) -> Option<Self> {
+        instructions(dialect).find(|i| i.byte == c).cloned()
+    }
+}
+
+/// Return all instructions supported in the given dialect.
+pub fn instruction_set(dialect: Dialect) -> Vec<InstructionInfo> {
+    instructions(dialect).cloned().collect()
+}
+
+/// Return whether the given byte is an instruction in the given dialect.
+///
+/// Unlike [`InstructionInfo::lookup`], this is a single table lookup, so it is cheap enough to be
+/// called on every step.
+///
+/// [`InstructionInfo::lookup`]: struct.InstructionInfo.html#method.lookup
+pub fn is_instruction(c: u8, dialect: Dialect) -> bool {
+    let table = match dialect {
+        Dialect::Befunge93 => &BEFUNGE93_TABLE,
+        Dialect::Funge98 => &FUNGE98_TABLE,
+    };
+
+    table[usize::from(c)]
+}
+
+fn instructions(dialect: Dialect) -> impl Iterator<Item = &'static InstructionInfo> {
+    let extensions = match dialect {
+        Dialect::Befunge93 => &[],
+        Dialect::Funge98 => FUNGE98,
+    };
+
+    BEFUNGE93.iter().chain(extensions)
+}
+
+const BEFUNGE93: &[InstructionInfo] = &[
+    InstructionInfo {
+        byte: instr::PUSH_0,
+        name: "push 0",
+        description: "Push 0 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_1,
+        name: "push 1",
+        description: "Push 1 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_2,
+        name: "push 2",
+        description: "Push 2 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_3,
+        name: "push 3",
+        description: "Push 3 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_4,
+        name: "push 4",
+        description: "Push 4 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_5,
+        name: "push 5",
+        description: "Push 5 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_6,
+        name: "push 6",
+        description: "Push 6 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_7,
+        name: "push 7",
+        description: "Push 7 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_8,
+        name: "push 8",
+        description: "Push 8 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_9,
+        name: "push 9",
+        description: "Push 9 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::ADD,
+        name: "add",
+        description: "Pop a and b, then push a+b",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::SUBTRACT,
+        name: "subtract",
+        description: "Pop a and b, then push b-a",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::MULTIPLY,
+        name: "multiply",
+        description: "Pop a and b, then push a*b",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::DIVIDE,
+        name: "divide",
+        description: "Pop a and b, then push b/a, rounded towards 0",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::MODULO,
+        name: "modulo",
+        description: "Pop a and b, then push the remainder of the integer division of b/a",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::NOT,
+        name: "not",
+        description: "Pop a value; push 1 if the value is zero, otherwise zero",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::GREATER,
+        name: "greater",
+        description: "Pop a and b, then push 1 if b>a, otherwise zero",
+        kind: InstructionKind::Operator,
+    },
+    InstructionInfo {
+        byte: instr::RIGHT,
+        name: "right",
+        description: "Start moving right",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::LEFT,
+        name: "left",
+        description: "Start moving left",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::UP,
+        name: "up",
+        description: "Start moving up",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::DOWN,
+        name: "down",
+        description: "Start moving down",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::RANDOM,
+        name: "random",
+        description: "Start moving in a random cardinal direction",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::HORIZONTAL_IF,
+        name: "horizontal if",
+        description: "Pop a value; move right if value=0, left otherwise",
+        kind: InstructionKind::Branch,
+    },
+    InstructionInfo {
+        byte: instr::VERTICAL_IF,
+        name: "vertical if",
+        description: "Pop a value; move down if value=0, up otherwise",
+        kind: InstructionKind::Branch,
+    },
+    InstructionInfo {
+        byte: instr::BRIDGE,
+        name: "bridge",
+        description: "Skip next cell",
+        kind: InstructionKind::Branch,
+    },
+    InstructionInfo {
+        byte: instr::STRING_MODE,
+        name: "string mode",
+        description: "Push each character's ASCII value all the way up to the next \"",
+        kind: InstructionKind::StringMode,
+    },
+    InstructionInfo {
+        byte: instr::DUPLICATE,
+        name: "duplicate",
+        description: "Duplicate value on top of the stack",
+        kind: InstructionKind::Stack,
+    },
+    InstructionInfo {
+        byte: instr::SWAP,
+        name: "swap",
+        description: "Swap two values on top of the stack",
+        kind: InstructionKind::Stack,
+    },
+    InstructionInfo {
+        byte: instr::DISCARD,
+        name: "discard",
+        description: "Pop value from the stack and discard it",
+        kind: InstructionKind::Stack,
+    },
+    InstructionInfo {
+        byte: instr::OUTPUT_INTEGER,
+        name: "output integer",
+        description: "Pop value and output as an integer followed by a space",
+        kind: InstructionKind::Io,
+    },
+    InstructionInfo {
+        byte: instr::OUTPUT_CHARACTER,
+        name: "output character",
+        description: "Pop value and output as ASCII character",
+        kind: InstructionKind::Io,
+    },
+    InstructionInfo {
+        byte: instr::INPUT_INTEGER,
+        name: "input integer",
+        description: "Ask user for a number and push it",
+        kind: InstructionKind::Io,
+    },
+    InstructionInfo {
+        byte: instr::INPUT_CHARACTER,
+        name: "input character",
+        description: "Ask user for a character and push its ASCII value",
+        kind: InstructionKind::Io,
+    },
+    InstructionInfo {
+        byte: instr::PUT,
+        name: "put",
+        description:
+            "Pop y, x and v, then change the character at (x,y) to the character with ASCII value v",
+        kind: InstructionKind::Storage,
+    },
+    InstructionInfo {
+        byte: instr::GET,
+        name: "get",
+        description: "Pop y and x, then push ASCII value of the character at that position",
+        kind: InstructionKind::Storage,
+    },
+    InstructionInfo {
+        byte: instr::TERMINATE,
+        name: "end",
+        description: "End program",
+        kind: InstructionKind::Terminate,
+    },
+];
+
+const FUNGE98: &[InstructionInfo] = &[
+    InstructionInfo {
+        byte: instr::PUSH_10,
+        name: "push 10",
+        description: "Push 10 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_11,
+        name: "push 11",
+        description: "Push 11 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_12,
+        name: "push 12",
+        description: "Push 12 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_13,
+        name: "push 13",
+        description: "Push 13 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_14,
+        name: "push 14",
+        description: "Push 14 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::PUSH_15,
+        name: "push 15",
+        description: "Push 15 on the stack",
+        kind: InstructionKind::Number,
+    },
+    InstructionInfo {
+        byte: instr::CLEAR,
+        name: "clear",
+        description: "Remove all values from the stack",
+        kind: InstructionKind::Stack,
+    },
+    InstructionInfo {
+        byte: instr::TURN_LEFT,
+        name: "turn left",
+        description: "Rotate the delta 90 degrees counterclockwise",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::TURN_RIGHT,
+        name: "turn right",
+        description: "Rotate the delta 90 degrees clockwise",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::REFLECT,
+        name: "reflect",
+        description: "Reverse the delta",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::ABSOLUTE_DELTA,
+        name: "absolute delta",
+        description: "Pop dy and dx, then set the delta to (dx,dy)",
+        kind: InstructionKind::Movement,
+    },
+    InstructionInfo {
+        byte: instr::COMPARE,
+        name: "compare",
+        description: "Pop b and a; turn left if a<b, turn right if a>b",
+        kind: InstructionKind::Branch,
+    },
+    InstructionInfo {
+        byte: instr::NO_OP,
+        name: "no-op",
+        description: "Does nothing",
+        kind: InstructionKind::NoOp,
+    },
+];
+
+const BEFUNGE93_TABLE: [bool; 256] = byte_table(&[BEFUNGE93]);
+const FUNGE98_TABLE: [bool; 256] = byte_table(&[BEFUNGE93, FUNGE98]);
+
+/// Build a table which marks the bytes of the given instructions.
+const fn byte_table(sets: &[&[InstructionInfo]]) -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut i = 0;
+
+    while i < sets.len() {
+        let mut j = 0;
+
+        while j < sets[i].len() {
+            table[sets[i][j].byte as usize] = true;
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_constants() {
+        assert_eq!(b'@', instr::TERMINATE);
+        assert_eq!(b'\\', instr::SWAP);
+        assert_eq!(b'z', instr::NO_OP);
+
+        for dialect in &[Dialect::Befunge93, Dialect::Funge98] {
+            for info in instruction_set(*dialect) {
+                assert_ne!(instr::SPACE, info.byte);
+            }
+
+            for c in 0..=u8::MAX {
+                assert_eq!(
+                    InstructionInfo::lookup(c, *dialect).is_some(),
+                    is_instruction(c, *dialect)
+                );
+            }
+        }
+
+        assert_eq!(
+            "add",
+            InstructionInfo::lookup(instr::ADD, Dialect::Befunge93)
+                .unwrap()
+                .name
+        );
+    }
+
+    #[test]
+    fn instruction_kind() {
+        assert_eq!(InstructionKind::Number, InstructionKind::of(b'7'));
+        assert_eq!(InstructionKind::Operator, InstructionKind::of(b'`'));
+        assert_eq!(InstructionKind::Movement, InstructionKind::of(b'?'));
+        assert_eq!(InstructionKind::Branch, InstructionKind::of(b'_'));
+        assert_eq!(InstructionKind::Terminate, InstructionKind::of(b'@'));
+        assert_eq!(InstructionKind::Stack, InstructionKind::of(b'\\'));
+        assert_eq!(InstructionKind::StringMode, InstructionKind::of(b'"'));
+        assert_eq!(InstructionKind::Io, InstructionKind::of(b'~'));
+        assert_eq!(InstructionKind::Storage, InstructionKind::of(b'g'));
+        assert_eq!(InstructionKind::NoOp, InstructionKind::of(b' '));
+        assert_eq!(InstructionKind::Illegal, InstructionKind::of(b'x'));
+    }
+
+    #[test]
+    fn befunge93_instruction_set() {
+        let set = instruction_set(Dialect::Befunge93);
+
+        let end = set.iter().find(|i| i.byte == b'@').unwrap();
+
+        assert_eq!("end", end.name);
+        assert_eq!(InstructionKind::Terminate, end.kind);
+
+        assert!(set.iter().all(|i| i.byte != b'['));
+        assert!(set.iter().all(|i| i.byte != b' '));
+        assert_eq!(36, set.len());
+    }
+
+    #[test]
+    fn funge98_instruction_set() {
+        let set = instruction_set(Dialect::Funge98);
+
+        assert!(set.iter().any(|i| i.byte == b'@'));
+        assert!(set.iter().any(|i| i.byte == b'['));
+        assert_eq!(49, set.len());
+
+        assert_eq!(None, InstructionInfo::lookup(b'x', Dialect::Befunge93));
+        assert!(!is_instruction(b'x', Dialect::Befunge93));
+        assert!(is_instruction(b'x', Dialect::Funge98));
+        assert_eq!(
+            InstructionKind::Movement,
+            InstructionInfo::lookup(b'x', Dialect::Funge98)
+                .unwrap()
+                .kind
+        );
+    }
+}
@@ -1,25 +1,348 @@
+pub mod blocks;
+pub mod scheduler;
+pub mod stack_ops;
+pub mod trace;
+
+use self::blocks::BasicBlocks;
+use crate::instruction::{self, instr, Dialect, InstructionInfo};
 use crate::playfield::*;
 use rand::distributions;
+use std::array;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
 use std::io;
 use std::io::prelude::*;
+use std::str;
+use std::time::Instant;
 
 /// The current mode of the program
 ///
 /// A program is either executing normally, parsing a string or has terminated.
-#[derive(Clone, Copy, Debug, PartialEq)]
+///
+/// While parsing a string, every byte except `"` is pushed onto the stack, including `@`. A
+/// string which is not terminated wraps around the playfield like any other movement and is
+/// eventually terminated by its own opening quote, after which execution continues normally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Mode {
     Execute,
     Parse,
     Terminate,
 }
 
+/// The side effect of a single step
+///
+/// Only effects which are visible outside of the stack and the navigator are reported.
+/// [`InputDefaulted`] is reported instead of [`InputRead`] if no value could be read because
+/// the input is exhausted or invalid, and a default value has been pushed instead.
+///
+/// [`InputDefaulted`]: #variant.InputDefaulted
+/// [`InputRead`]: #variant.InputRead
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepEffect {
+    None,
+    OutputWritten,
+    InputRead,
+    InputDefaulted,
+    FieldModified,
+}
+
+/// The outcome of a single step
+///
+/// A step is trapped if the instruction about to be executed is trapped or if a breakpoint is
+/// hit. In that case, the instruction is not executed and the next step resumes execution at
+/// the same position.
+///
+/// `TimedOut` is only returned by [`Interpreter::run_to_completion_until`] if the deadline
+/// passes before the program terminates.
+///
+/// [`Interpreter::run_to_completion_until`]: struct.Interpreter.html#method.run_to_completion_until
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepOutcome {
+    Continue,
+    Terminated,
+    Trapped((usize, usize)),
+    TimedOut,
+}
+
+/// A record of a single step, see [`Interpreter::step_record`]
+///
+/// [`Interpreter::step_record`]: struct.Interpreter.html#method.step_record
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepRecord {
+    /// The position of the cell the step started on
+    pub pos: (usize, usize),
+    /// The byte stored in that cell
+    pub instruction: u8,
+    /// The mode before the step
+    pub mode_before: Mode,
+    /// The mode after the step
+    pub mode_after: Mode,
+    /// The stack after the step
+    pub stack: Stack,
+    /// The side effect of the step
+    pub effect: StepEffect,
+    /// The outcome of the step
+    pub outcome: StepOutcome,
+}
+
+/// An error which prevents a step from being executed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepError {
+    /// The byte at the given position is no instruction in the current dialect.
+    IllegalInstruction {
+        pos: (usize, usize),
+        instruction: u8,
+    },
+    /// The `p` instruction at the given position would write into a protected cell.
+    ProtectedWrite {
+        pos: (usize, usize),
+        target: (usize, usize),
+    },
+    /// The instruction at the given position would pop from an empty stack.
+    StackUnderflow {
+        pos: (usize, usize),
+        instruction: u8,
+    },
+    /// The `/` or `%` instruction at the given position would divide by zero.
+    DivideByZero {
+        pos: (usize, usize),
+        instruction: u8,
+    },
+    /// The `p` instruction at the given position would grow the playfield beyond its limit.
+    FieldLimitExceeded {
+        pos: (usize, usize),
+        target: (i64, i64),
+    },
+}
+
+impl StepError {
+    /// Return the position of the instruction which failed.
+    pub fn pos(&self) -> (usize, usize) {
+        match *self {
+            StepError::IllegalInstruction { pos, .. }
+            | StepError::ProtectedWrite { pos, .. }
+            | StepError::StackUnderflow { pos, .. }
+            | StepError::DivideByZero { pos, .. }
+            | StepError::FieldLimitExceeded { pos, .. } => pos,
+        }
+    }
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StepError::IllegalInstruction { instruction, .. } => {
+                write!(f, "Illegal character: {}", *instruction as char)
+            }
+            StepError::ProtectedWrite { target, .. } => {
+                write!(f, "Write into protected cell: ({}, {})", target.0, target.1)
+            }
+            StepError::StackUnderflow { instruction, .. } => {
+                write!(f, "Stack underflow: {}", *instruction as char)
+            }
+            StepError::DivideByZero { .. } => write!(f, "Division by zero"),
+            StepError::FieldLimitExceeded { target, .. } => write!(
+                f,
+                "Write beyond the playfield limit: ({}, {})",
+                target.0, target.1
+            ),
+        }
+    }
+}
+
+impl error::Error for StepError {}
+
+/// The semantics of the `/` and `%` instructions for negative operands
+///
+/// Division and modulo always agree, so `b == a * (b / a) + b % a` holds for all nonzero `a`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ModuloMode {
+    /// Round the quotient towards zero, so the remainder has the sign of the dividend and
+    /// `-7 % 3` is `-1`.
+    #[default]
+    Rust,
+    /// Use Euclidean division, so the remainder is never negative and `-7 % 3` is `2`.
+    Euclidean,
+}
+
+/// The way the interpreter handles illegal instructions
+///
+/// Wide cells holding a value beyond a byte are illegal instructions as well.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IllegalPolicy {
+    /// Return a [`StepError`] without executing the instruction.
+    ///
+    /// [`StepError`]: enum.StepError.html
+    #[default]
+    Fail,
+    /// Record the position and the byte of the instruction and treat it as a no-op.
+    Skip,
+}
+
+/// The way the interpreter handles division and modulo by zero
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DivByZeroPolicy {
+    /// Pop both operands and push zero.
+    #[default]
+    Zero,
+    /// Pop both operands and reverse the direction of the instruction pointer.
+    Reflect,
+    /// Return a [`StepError`] without executing the instruction.
+    ///
+    /// [`StepError`]: enum.StepError.html
+    Fail,
+}
+
+/// The way the interpreter handles a `p` which would grow the playfield beyond its limit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GrowPolicy {
+    /// Ignore the write, as for writes outside of the playfield without auto-growing.
+    #[default]
+    Ignore,
+    /// Return a [`StepError`] without executing the instruction.
+    ///
+    /// [`StepError`]: enum.StepError.html
+    Fail,
+}
+
+/// The default maximum number of cells of an auto-growing playfield
+pub const DEFAULT_MAX_FIELD_CELLS: usize = 1 << 24;
+
+/// The way the interpreter handles popping from an empty stack
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EmptyPopPolicy {
+    /// Pop a zero, as defined by Befunge-93.
+    #[default]
+    Zero,
+    /// Return a [`StepError`] without executing the instruction.
+    ///
+    /// [`StepError`]: enum.StepError.html
+    Error,
+}
+
+/// A warning about suspicious behavior of a program
+///
+/// Warnings are only emitted in strict mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Warning {
+    /// A value outside of `0..=255` has been truncated when stored in the playfield.
+    TruncatedPut { pos: (usize, usize), value: i64 },
+}
+
+/// An observer of the execution of an interpreter
+///
+/// All methods have empty default implementations, so implementors only need to override the
+/// events they are interested in.
+pub trait Observer: Send {
+    /// Called whenever the interpreter emits a warning.
+    fn on_warning(&mut self, _warning: Warning) {}
+
+    /// Called whenever an input instruction at the given position pushes a default value
+    /// because the input is exhausted or invalid.
+    ///
+    /// Unlike warnings, this is also called outside of strict mode.
+    fn on_input_defaulted(&mut self, _pos: (usize, usize)) {}
+
+    /// Called whenever a step changes the mode of the interpreter.
+    ///
+    /// Restoring a snapshot does not count as a step.
+    fn on_mode_change(&mut self, _from: Mode, _to: Mode) {}
+}
+
 /// The stack of an execution.
 pub type Stack = Vec<i64>;
 
+/// Format the given stack with the bottom first.
+///
+/// Each value is shown in decimal and, if it is a printable ASCII character, also as that
+/// character, e.g. `[72 'H', 300]`.
+pub fn format_stack(stack: &[i64]) -> String {
+    let values: Vec<String> = stack.iter().map(|&v| format_value(v)).collect();
+
+    format!("[{}]", values.join(", "))
+}
+
+/// Format the given stack with one value per line, each prefixed with its index.
+///
+/// The bottom of the stack has index 0. If `top_first` is set, the top of the stack is on the
+/// first line, otherwise it is on the last line. Values are formatted as in [`format_stack`].
+///
+/// [`format_stack`]: fn.format_stack.html
+pub fn format_stack_vertical(stack: &[i64], top_first: bool) -> String {
+    let mut lines: Vec<String> = stack
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| format!("{}: {}", i, format_value(v)))
+        .collect();
+
+    if top_first {
+        lines.reverse();
+    }
+
+    lines.join("\n")
+}
+
+fn format_value(v: i64) -> String {
+    match v {
+        0x20..=0x7e => format!("{} '{}'", v, v as u8 as char),
+        _ => v.to_string(),
+    }
+}
+
+/// Check whether the given programs behave the same on the given input.
+///
+/// Both programs are executed for at most `max_steps` steps. They behave the same if they
+/// write the same output and end in the same way, i.e. both terminate, both are still running
+/// or both encounter an illegal instruction.
+pub fn outputs_equal(a: &str, b: &str, input: &str, max_steps: usize) -> bool {
+    let run = |program: &str| {
+        let io = InputOutput::new(input.as_bytes(), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new(program), io);
+
+        let status = interpreter.run_with_limit(max_steps).map_err(|_| ());
+
+        (status, interpreter.io().writer().clone())
+    };
+
+    run(a) == run(b)
+}
+
+/// The encoding of characters written by the `,` instruction
+///
+/// With [`Raw`], each value is written as the character with that code point. With
+/// [`Utf8Checked`], each value is treated as a single byte of UTF-8 encoded output. Bytes are
+/// buffered until they form a complete sequence and invalid sequences are replaced with
+/// U+FFFD.
+///
+/// [`Raw`]: #variant.Raw
+/// [`Utf8Checked`]: #variant.Utf8Checked
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputEncoding {
+    #[default]
+    Raw,
+    Utf8Checked,
+}
+
+/// The separator written after each integer by the `.` instruction
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IntSeparator {
+    #[default]
+    Space,
+    Newline,
+    None,
+}
+
 /// A provider of input and output operations.
 pub struct InputOutput<R, W> {
     reader: R,
     writer: W,
+    separator: IntSeparator,
+    encoding: OutputEncoding,
+    pending: Vec<u8>,
+    echo: bool,
+    discard_ints: bool,
 }
 
 impl<R, W> InputOutput<R, W>
@@ -29,7 +352,15 @@ where
 {
     /// Create a new input and output provider based on the given reader and writer.
     pub fn new(reader: R, writer: W) -> Self {
-        Self { reader, writer }
+        Self {
+            reader,
+            writer,
+            separator: IntSeparator::Space,
+            encoding: OutputEncoding::Raw,
+            pending: Vec::new(),
+            echo: false,
+            discard_ints: false,
+        }
     }
 
     /// Return the input provider.
@@ -42,32 +373,189 @@ where
         &self.writer
     }
 
+    /// Return a mutable reference to the input provider.
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Return a mutable reference to the output provider.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Return the separator written after integers.
+    pub fn int_separator(&self) -> IntSeparator {
+        self.separator
+    }
+
+    /// Set the separator written after integers. A space is written by default.
+    pub fn set_int_separator(&mut self, separator: IntSeparator) {
+        self.separator = separator;
+    }
+
+    /// Return the encoding of written characters.
+    pub fn output_encoding(&self) -> OutputEncoding {
+        self.encoding
+    }
+
+    /// Set the encoding of written characters.
+    ///
+    /// Bytes of an incomplete UTF-8 sequence which are still buffered are discarded.
+    pub fn set_output_encoding(&mut self, encoding: OutputEncoding) {
+        self.encoding = encoding;
+        self.pending.clear();
+    }
+
+    /// Return whether consumed input is echoed to the output.
+    pub fn echo(&self) -> bool {
+        self.echo
+    }
+
+    /// Enable or disable echoing consumed input to the output.
+    ///
+    /// Every byte consumed by `&` or `~` is written unchanged, so a transcript of the output
+    /// shows the input interleaved with it. Echoing is disabled by default.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    /// Return whether integers written by `.` are discarded.
+    pub fn discard_ints(&self) -> bool {
+        self.discard_ints
+    }
+
+    /// Enable or disable discarding integers written by `.`.
+    ///
+    /// When enabled, the output only consists of the characters written by `,`, which changes
+    /// the observable output of programs using `.`. Integers are written by default.
+    pub fn set_discard_ints(&mut self, discard_ints: bool) {
+        self.discard_ints = discard_ints;
+    }
+
+    /// Write buffered bytes of an incomplete UTF-8 sequence as U+FFFD.
+    ///
+    /// This is done automatically before writing an integer and when the program terminates.
+    pub fn flush_pending(&mut self) {
+        if !self.pending.is_empty() {
+            self.pending.clear();
+
+            write!(self.writer, "\u{fffd}").unwrap()
+        }
+    }
+
     fn write_int(&mut self, val: i64) {
-        write!(self.writer, "{} ", val).unwrap()
+        if self.discard_ints {
+            return;
+        }
+
+        self.flush_pending();
+
+        let separator = match self.separator {
+            IntSeparator::Space => " ",
+            IntSeparator::Newline => "\n",
+            IntSeparator::None => "",
+        };
+
+        write!(self.writer, "{}{}", val, separator).unwrap()
     }
 
     fn write_ascii(&mut self, val: i64) {
-        write!(self.writer, "{}", val as u8 as char).unwrap()
+        match self.encoding {
+            OutputEncoding::Raw => write!(self.writer, "{}", val as u8 as char).unwrap(),
+            OutputEncoding::Utf8Checked => {
+                self.pending.push(val as u8);
+                self.write_pending();
+            }
+        }
+    }
+
+    fn write_pending(&mut self) {
+        loop {
+            let err = match str::from_utf8(&self.pending) {
+                Ok(s) => {
+                    self.writer.write_all(s.as_bytes()).unwrap();
+                    self.pending.clear();
+
+                    return;
+                }
+                Err(err) => err,
+            };
+
+            let valid = err.valid_up_to();
+
+            self.writer.write_all(&self.pending[..valid]).unwrap();
+
+            match err.error_len() {
+                Some(len) => {
+                    write!(self.writer, "\u{fffd}").unwrap();
+                    self.pending.drain(..valid + len);
+                }
+                None => {
+                    // wait for the remaining bytes of the sequence
+                    self.pending.drain(..valid);
+
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Read the next whitespace-delimited token and parse it as an integer.
+    ///
+    /// Multiple integers can be given on a single line. A line break directly following the
+    /// token is consumed as well. Returns `None` if the token is not a valid integer or the
+    /// input is exhausted.
+    fn read_int(&mut self) -> Option<i64> {
+        while let Some(b) = self.peek_byte().filter(|b| b.is_ascii_whitespace()) {
+            self.consume(b);
+        }
+
+        let mut token = Vec::new();
+
+        while let Some(b) = self.peek_byte().filter(|b| !b.is_ascii_whitespace()) {
+            token.push(b);
+            self.consume(b);
+        }
+
+        if let Some(b'\r') = self.peek_byte() {
+            self.consume(b'\r');
+        }
+
+        if let Some(b'\n') = self.peek_byte() {
+            self.consume(b'\n');
+        }
+
+        String::from_utf8_lossy(&token).parse().ok()
     }
 
-    fn read_int(&mut self) -> i64 {
-        let mut input = String::new();
+    fn consume(&mut self, b: u8) {
+        self.reader.consume(1);
 
-        if self.reader.read_line(&mut input).is_err() {
-            return 0;
+        if self.echo {
+            self.writer.write_all(&[b]).unwrap();
         }
+    }
 
-        input.trim().parse().unwrap_or(0)
+    fn peek_byte(&mut self) -> Option<u8> {
+        match self.reader.fill_buf() {
+            Ok(buf) => buf.first().cloned(),
+            Err(_) => None,
+        }
     }
 
-    fn read_ascii(&mut self) -> i64 {
+    /// Read a single byte. Returns `None` if the input is exhausted.
+    fn read_ascii(&mut self) -> Option<i64> {
         let mut buf = [0; 1];
 
         if self.reader.read_exact(&mut buf).is_err() {
-            return -1;
+            return None;
+        }
+
+        if self.echo {
+            self.writer.write_all(&buf).unwrap();
         }
 
-        i64::from(buf[0])
+        Some(i64::from(buf[0]))
     }
 }
 
@@ -82,6 +570,61 @@ impl Default for StdInputOutput {
     }
 }
 
+/// An [`InputOutput`] implementation without any input which discards all output.
+///
+/// [`InputOutput`]: struct.InputOutput.html
+pub type NullInputOutput = InputOutput<io::Empty, io::Sink>;
+
+impl Default for NullInputOutput {
+    fn default() -> Self {
+        Self::new(io::empty(), io::sink())
+    }
+}
+
+/// A snapshot of the execution state of an interpreter
+///
+/// The state covers the playfield, the navigator, the stack and the mode, but not the input
+/// and output provider.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterpreterState {
+    field: Playfield,
+    nav: PlayfieldNavigator,
+    stack: Stack,
+    mode: Mode,
+    parse_len: usize,
+    steps: usize,
+    movement: [u64; 4],
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Breakpoint {
+    count: Option<usize>,
+    hits: usize,
+}
+
+/// A description of a configured breakpoint
+///
+/// Positional breakpoints are listed with their hit count, if any, and the number of times the
+/// cell has been executed so far. Instruction breakpoints are the traps added with [`Interpreter::add_trap`].
+///
+/// [`Interpreter::add_trap`]: struct.Interpreter.html#method.add_trap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BreakpointInfo {
+    Position {
+        pos: (usize, usize),
+        count: Option<usize>,
+        hits: usize,
+    },
+    Instruction(u8),
+}
+
+/// The maximum number of steps executed by [`Interpreter::step_until_row_change`] and
+/// [`Interpreter::step_until_col_change`]
+///
+/// [`Interpreter::step_until_row_change`]: struct.Interpreter.html#method.step_until_row_change
+/// [`Interpreter::step_until_col_change`]: struct.Interpreter.html#method.step_until_col_change
+pub const STEP_UNTIL_LIMIT: usize = 100_000;
+
 /// A Befunge interpreter
 pub struct Interpreter<R, W> {
     field: Playfield,
@@ -89,6 +632,34 @@ pub struct Interpreter<R, W> {
     nav: PlayfieldNavigator,
     stack: Stack,
     mode: Mode,
+    parse_len: usize,
+    last_effect: StepEffect,
+    steps: usize,
+    movement: [u64; 4],
+    max_stack_depth: usize,
+    traps: HashSet<u8>,
+    no_ops: HashSet<u8>,
+    aliases: HashMap<u8, u8>,
+    forced_random: HashMap<(usize, usize), (Direction, bool)>,
+    breakpoints: HashMap<(usize, usize), Breakpoint>,
+    resume: bool,
+    history: VecDeque<InterpreterState>,
+    history_depth: usize,
+    checkpoints: HashMap<String, InterpreterState>,
+    coverage: Option<Vec<u32>>,
+    observer: Option<Box<dyn Observer>>,
+    strict: bool,
+    dialect: Dialect,
+    illegal_policy: IllegalPolicy,
+    empty_pop: EmptyPopPolicy,
+    auto_grow: bool,
+    max_field_cells: usize,
+    grow_policy: GrowPolicy,
+    skipped_illegals: Vec<(usize, usize, u8)>,
+    protected: Vec<((usize, usize), (usize, usize))>,
+    modulo: ModuloMode,
+    div_by_zero: DivByZeroPolicy,
+    deadline_interval: usize,
 }
 
 impl<R, W> Interpreter<R, W>
@@ -98,14 +669,50 @@ where
 {
     /// Create a new interpreter for the given playfield.
     pub fn new(field: Playfield, io: InputOutput<R, W>) -> Self {
+        Self::with_stack(field, io, Vec::new())
+    }
+
+    /// Create a new interpreter for the given playfield with an initial stack.
+    ///
+    /// The last value of the stack is on top.
+    pub fn with_stack(field: Playfield, io: InputOutput<R, W>, stack: Stack) -> Self {
         let dimensions = field.dimensions();
+        let max_stack_depth = stack.len();
 
         Self {
             field,
             io,
             nav: PlayfieldNavigator::new(dimensions),
-            stack: Vec::new(),
+            stack,
             mode: Mode::Execute,
+            parse_len: 0,
+            last_effect: StepEffect::None,
+            steps: 0,
+            movement: [0; 4],
+            max_stack_depth,
+            traps: HashSet::new(),
+            no_ops: HashSet::new(),
+            aliases: HashMap::new(),
+            forced_random: HashMap::new(),
+            breakpoints: HashMap::new(),
+            resume: false,
+            history: VecDeque::new(),
+            history_depth: 0,
+            checkpoints: HashMap::new(),
+            coverage: None,
+            observer: None,
+            strict: false,
+            dialect: Dialect::Befunge93,
+            illegal_policy: IllegalPolicy::Fail,
+            empty_pop: EmptyPopPolicy::Zero,
+            auto_grow: false,
+            max_field_cells: DEFAULT_MAX_FIELD_CELLS,
+            grow_policy: GrowPolicy::Ignore,
+            skipped_illegals: Vec::new(),
+            protected: Vec::new(),
+            modulo: ModuloMode::Rust,
+            div_by_zero: DivByZeroPolicy::Zero,
+            deadline_interval: 1000,
         }
     }
 
@@ -119,6 +726,11 @@ where
         &self.io
     }
 
+    /// Get a mutable reference to the input and output provider.
+    pub fn io_mut(&mut self) -> &mut InputOutput<R, W> {
+        &mut self.io
+    }
+
     /// Get a reference to the navigator.
     pub fn nav(&self) -> &PlayfieldNavigator {
         &self.nav
@@ -134,701 +746,3713 @@ where
         self.mode
     }
 
-    fn execute_step(&mut self, c: u8) -> Mode {
-        match c {
-            // Push this number on the stack
-            b'0'...b'9' => self.stack.push(i64::from(c - 0x30)),
+    /// Get the number of characters pushed since entering string mode.
+    ///
+    /// Returns `None` if the interpreter is not currently parsing a string.
+    pub fn parse_len(&self) -> Option<usize> {
+        match self.mode {
+            Mode::Parse => Some(self.parse_len),
+            _ => None,
+        }
+    }
 
-            // Addition: Pop a and b, then push a+b
-            b'+' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+    /// Get the characters pushed since entering string mode as a string.
+    ///
+    /// These are the top [`parse_len`] values of the stack, each truncated to a byte and
+    /// decoded lossily as UTF-8. Returns `None` if the interpreter is not currently parsing a
+    /// string.
+    ///
+    /// [`parse_len`]: #method.parse_len
+    pub fn pending_string(&self) -> Option<String> {
+        let len = self.parse_len()?;
+        let bytes: Vec<u8> = self.stack[self.stack.len().saturating_sub(len)..]
+            .iter()
+            .map(|&v| v as u8)
+            .collect();
 
-                self.stack.push(a + b);
-            }
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
 
-            // Subtraction: Pop a and b, then push b-a
-            b'-' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+    /// Get the number of moves in each direction so far, indexed by [`Direction::as_u8`].
+    ///
+    /// A move is counted after every step which does not terminate the program. Moves with a
+    /// delta which is no cardinal direction are not counted.
+    ///
+    /// [`Direction::as_u8`]: ../playfield/enum.Direction.html#method.as_u8
+    pub fn movement_stats(&self) -> [u64; 4] {
+        self.movement
+    }
 
-                self.stack.push(b - a);
-            }
+    /// Get the number of times the navigator wrapped around an edge of the playfield so far.
+    ///
+    /// In Lahey-space, wrapping around the content bounds counts as well.
+    pub fn wrap_count(&self) -> u64 {
+        self.nav.wrap_count()
+    }
 
-            // Multiplication: Pop a and b, then push a*b
-            b'*' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+    /// Get the whole stack as a string in the order a `,` loop would print it, i.e. with the
+    /// top of the stack first.
+    ///
+    /// Each value is treated as a byte and the bytes are decoded lossily as UTF-8. Values
+    /// outside of `0..=255` are decoded as U+FFFD as well.
+    pub fn stack_as_string(&self) -> String {
+        let bytes: Vec<u8> = self
+            .stack
+            .iter()
+            .rev()
+            .map(|&v| {
+                if (0..=255).contains(&v) {
+                    v as u8
+                } else {
+                    0xff
+                }
+            })
+            .collect();
 
-                self.stack.push(a * b);
-            }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
 
-            // Integer division: Pop a and b, then push b/a, rounded towards 0
-            b'/' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+    /// Write a plain text frame of the current state to the given writer.
+    ///
+    /// The frame consists of the playfield with the instruction pointer marked as in
+    /// [`Playfield::render_with_cursor`], followed by a line with the stack as formatted by
+    /// [`format_stack`]. This allows logging frames without a terminal user interface.
+    ///
+    /// [`Playfield::render_with_cursor`]: ../playfield/struct.Playfield.html#method.render_with_cursor
+    /// [`format_stack`]: fn.format_stack.html
+    pub fn render_frame<O: Write>(&self, w: &mut O) -> io::Result<()> {
+        w.write_all(self.field.render_with_cursor(self.nav.pos()).as_bytes())?;
+        writeln!(w, "stack: {}", format_stack(&self.stack))
+    }
 
-                self.stack.push(b / a);
-            }
+    /// Get the number of steps executed so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
 
-            // Modulo: Pop a and b, then push the remainder of the integer division of b/a
-            b'%' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+    /// Get the maximum depth the stack has reached so far.
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_stack_depth
+    }
 
-                self.stack.push(b % a);
-            }
+    /// Take a snapshot of the current execution state.
+    pub fn snapshot(&self) -> InterpreterState {
+        InterpreterState {
+            field: self.field.clone(),
+            nav: self.nav.clone(),
+            stack: self.stack.clone(),
+            mode: self.mode,
+            parse_len: self.parse_len,
+            steps: self.steps,
+            movement: self.movement,
+        }
+    }
 
-            // Logical NOT: Pop a value. If the value is zero, push 1; otherwise, push zero.
-            b'!' => {
-                if self.stack.pop().unwrap_or(0) == 0 {
-                    self.stack.push(1)
-                } else {
-                    self.stack.push(0)
-                }
-            }
+    /// Restore a previously taken snapshot.
+    ///
+    /// Input which has been read and output which has been written are not affected.
+    pub fn restore(&mut self, state: InterpreterState) {
+        let old = self.field.dimensions();
 
-            // Greater than: Pop a and b, then push 1 if b>a, otherwise zero.
-            b'`' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+        self.field = state.field;
+        self.nav = state.nav;
+        self.stack = state.stack;
+        self.mode = state.mode;
+        self.parse_len = state.parse_len;
+        self.steps = state.steps;
+        self.movement = state.movement;
+        self.last_effect = StepEffect::None;
+        self.resume = false;
 
-                if b > a {
-                    self.stack.push(1)
-                } else {
-                    self.stack.push(0)
-                }
-            }
+        self.update_dimensions(old);
+    }
 
-            // Start moving right
-            b'>' => self.nav.turn(Direction::Right),
+    /// Create a speculative copy of this interpreter without real input and output.
+    ///
+    /// The copy starts from the current execution state and uses the same dialect, execution
+    /// policies, protected regions and input echoing, but it reads no input and discards all
+    /// output, so it can be run ahead without side effects. Traps, breakpoints, history,
+    /// checkpoints, coverage and the observer are not copied.
+    pub fn speculate(&self) -> Interpreter<io::Empty, io::Sink> {
+        let mut io = NullInputOutput::default();
 
-            // Start moving left
-            b'<' => self.nav.turn(Direction::Left),
+        io.set_echo(self.io.echo());
 
-            // Start moving up
-            b'^' => self.nav.turn(Direction::Up),
+        Interpreter {
+            field: self.field.clone(),
+            io,
+            nav: self.nav.clone(),
+            stack: self.stack.clone(),
+            mode: self.mode,
+            parse_len: self.parse_len,
+            last_effect: StepEffect::None,
+            steps: self.steps,
+            movement: self.movement,
+            max_stack_depth: self.max_stack_depth,
+            traps: HashSet::new(),
+            no_ops: self.no_ops.clone(),
+            aliases: self.aliases.clone(),
+            forced_random: self.forced_random.clone(),
+            breakpoints: HashMap::new(),
+            resume: false,
+            history: VecDeque::new(),
+            history_depth: 0,
+            checkpoints: HashMap::new(),
+            coverage: None,
+            observer: None,
+            strict: self.strict,
+            dialect: self.dialect,
+            illegal_policy: self.illegal_policy,
+            empty_pop: self.empty_pop,
+            auto_grow: self.auto_grow,
+            max_field_cells: self.max_field_cells,
+            grow_policy: self.grow_policy,
+            skipped_illegals: Vec::new(),
+            protected: self.protected.clone(),
+            modulo: self.modulo,
+            div_by_zero: self.div_by_zero,
+            deadline_interval: self.deadline_interval,
+        }
+    }
 
-            // Start moving down
-            b'v' => self.nav.turn(Direction::Down),
+    /// Resize the playfield to the given dimensions, see [`Playfield::resize`].
+    ///
+    /// The instruction pointer is moved into the new dimensions if necessary and the tracked
+    /// coverage of removed cells is discarded.
+    ///
+    /// [`Playfield::resize`]: ../playfield/struct.Playfield.html#method.resize
+    pub fn resize_field(&mut self, width: usize, height: usize) {
+        let old = self.field.dimensions();
 
-            // Start moving in a random cardinal direction
-            b'?' => self.nav.turn(rand::random()),
+        self.field.resize(width, height);
+        self.update_dimensions(old);
+    }
 
-            // Pop a value; move right if value=0, left otherwise
-            b'_' => {
-                if self.stack.pop().unwrap_or(0) == 0 {
-                    self.nav.turn(Direction::Right)
-                } else {
-                    self.nav.turn(Direction::Left)
-                }
-            }
+    fn update_dimensions(&mut self, old: (usize, usize)) {
+        let (width, height) = self.field.dimensions();
 
-            // Pop a value; move down if value=0, up otherwise
-            b'|' => {
-                if self.stack.pop().unwrap_or(0) == 0 {
-                    self.nav.turn(Direction::Down)
-                } else {
-                    self.nav.turn(Direction::Up)
-                }
+        if let Some(coverage) = &mut self.coverage {
+            if (width, height) != old {
+                *coverage = (0..width * height)
+                    .map(|i| (i % width, i / width))
+                    .map(|(x, y)| {
+                        if x < old.0 && y < old.1 {
+                            coverage[x + old.0 * y]
+                        } else {
+                            0
+                        }
+                    })
+                    .collect();
             }
+        }
 
-            // Start string mode: push each character's ASCII value all the way up to the next "
-            b'"' => return Mode::Parse,
-
-            // Duplicate value on top of the stack
-            b':' => {
-                let v = self.stack.pop().unwrap_or(0);
+        self.nav.set_dimensions((width, height));
 
-                self.stack.push(v);
-                self.stack.push(v);
-            }
+        if let WrapMode::LaheySpace = self.nav.wrap_mode() {
+            self.set_wrap_mode(WrapMode::LaheySpace);
+        }
 
-            // Swap two values on top of the stack
-            b'\\' => {
-                let a = self.stack.pop().unwrap_or(0);
-                let b = self.stack.pop().unwrap_or(0);
+        self.assert_consistent();
+    }
 
-                self.stack.push(a);
-                self.stack.push(b);
-            }
+    /// Check that the navigator and the coverage match the dimensions of the playfield.
+    ///
+    /// This is called after operations which change the dimensions, but only checked in debug
+    /// builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the navigator wraps around other dimensions than those of the
+    /// playfield, if the instruction pointer is outside of the playfield or if the coverage
+    /// has the wrong size.
+    pub fn assert_consistent(&self) {
+        let (width, height) = self.field.dimensions();
+        let (x, y) = self.nav.pos();
 
-            // Pop value from the stack and discard it
-            b'$' => {
-                self.stack.pop();
-            }
+        debug_assert_eq!(
+            (width, height),
+            self.nav.dimensions(),
+            "navigator dimensions"
+        );
+        debug_assert!(
+            x < width && y < height,
+            "position {:?} out of bounds",
+            (x, y)
+        );
+        debug_assert!(
+            self.coverage
+                .as_ref()
+                .map_or(true, |c| c.len() == width * height),
+            "coverage size"
+        );
+    }
 
-            // Pop value and output as an integer followed by a space
-            b'.' => self.io.write_int(self.stack.pop().unwrap_or(0)),
+    /// Set the number of steps to keep in the history.
+    ///
+    /// The history is disabled by default. Keeping a history takes a snapshot before every step.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
 
-            // Pop value and output as ASCII character
-            b',' => self.io.write_ascii(self.stack.pop().unwrap_or(0)),
+        while self.history.len() > depth {
+            self.history.pop_front();
+        }
+    }
 
-            // Bridge: Skip next cell
-            b'#' => self.nav.step(),
+    /// Get the number of steps which can currently be undone.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
 
-            // A "put" call (a way to store a value for later use).
-            //
-            // Pop y, x, and v, then change the character at (x,y) in the program to the character
-            // with ASCII value v
-            b'p' => {
-                let y = self.stack.pop().unwrap_or(0);
-                let x = self.stack.pop().unwrap_or(0);
-                let v = self.stack.pop().unwrap_or(0);
+    /// Undo up to `n` steps using the history.
+    ///
+    /// Returns the number of steps which have actually been undone. Input and output are not
+    /// reverted, so rewinding over I/O instructions only restores the execution state.
+    pub fn rewind(&mut self, n: usize) -> usize {
+        let mut undone = 0;
 
-                self.field[(x as usize, y as usize)] = v as u8
+        while undone < n {
+            match self.history.pop_back() {
+                Some(state) => self.restore(state),
+                None => break,
             }
 
-            // A "get" call (a way to retrieve data in storage).
-            //
-            // Pop y and x, then push ASCII value of the character at that position in the program
-            b'g' => {
-                let y = self.stack.pop().unwrap_or(0);
-                let x = self.stack.pop().unwrap_or(0);
-                let v = self.field[(x as usize, y as usize)];
+            undone += 1;
+        }
 
-                self.stack.push(i64::from(v))
-            }
+        undone
+    }
 
-            // Ask user for a number and push it
-            b'&' => self.stack.push(self.io.read_int()),
+    /// Store a snapshot of the current execution state under the given name.
+    ///
+    /// An existing checkpoint with the same name is replaced.
+    pub fn checkpoint(&mut self, name: &str) {
+        let state = self.snapshot();
 
-            // Ask user for a character and push its ASCII value
-            b'~' => self.stack.push(self.io.read_ascii()),
+        self.checkpoints.insert(name.to_string(), state);
+    }
 
-            // End program
-            b'@' => return Mode::Terminate,
+    /// Restore the checkpoint with the given name.
+    ///
+    /// The checkpoint is kept, so it can be rolled back to multiple times. Returns `false` if
+    /// there is no checkpoint with that name.
+    pub fn rollback(&mut self, name: &str) -> bool {
+        match self.checkpoints.get(name) {
+            Some(state) => {
+                let state = state.clone();
 
-            // No-op. Does nothing
-            b' ' => (),
+                self.restore(state);
 
-            // Illegal characters
-            _ => panic!("Illegal character: {}", c as char),
+                true
+            }
+            None => false,
         }
+    }
 
-        Mode::Execute
+    /// Get the dialect of the interpreter.
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
     }
 
-    fn parse_step(&mut self, c: u8) -> Mode {
-        if let b'"' = c {
-            return Mode::Execute;
-        }
+    /// Set the dialect of the interpreter.
+    ///
+    /// Instructions which are not part of the dialect are illegal. Befunge-93 is the default.
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+    }
 
-        self.stack.push(i64::from(c));
+    /// Set the way the instruction pointer wraps around the playfield.
+    ///
+    /// In Lahey-space, the content bounds are updated whenever the playfield is modified.
+    pub fn set_wrap_mode(&mut self, wrap: WrapMode) {
+        self.nav.set_wrap_mode(wrap, self.field.content_bounds());
+    }
 
-        Mode::Parse
+    /// Enable or disable counting how often each cell has been executed.
+    ///
+    /// Coverage tracking is disabled by default, so there is no overhead unless it is needed.
+    /// Enabling it discards previously tracked counts.
+    pub fn set_coverage_tracking(&mut self, enabled: bool) {
+        self.coverage = if enabled {
+            Some(vec![0; self.field.width() * self.field.height()])
+        } else {
+            None
+        };
     }
-}
 
-impl<R, W> Iterator for Interpreter<R, W>
-where
-    R: BufRead,
-    W: Write,
-{
-    type Item = ();
+    /// Get the positions of all cells executed since coverage tracking was enabled.
+    ///
+    /// Cells within string literals count as executed. Returns an empty set if coverage
+    /// tracking is disabled.
+    pub fn executed_cells(&self) -> HashSet<(usize, usize)> {
+        let width = self.field.width();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let val = self.field[self.nav.pos()];
+        match &self.coverage {
+            Some(coverage) => coverage
+                .iter()
+                .enumerate()
+                .filter(|(_, &n)| n > 0)
+                .map(|(i, _)| (i % width, i / width))
+                .collect(),
+            None => HashSet::new(),
+        }
+    }
 
-        self.mode = match self.mode {
-            Mode::Execute => self.execute_step(val),
-            Mode::Parse => self.parse_step(val),
-            Mode::Terminate => Mode::Terminate,
-        };
+    /// Get the number of times the cell at `(x, y)` has been executed.
+    ///
+    /// Returns zero if coverage tracking is disabled or the position lies outside of the
+    /// playfield.
+    pub fn execution_count(&self, x: usize, y: usize) -> u32 {
+        match &self.coverage {
+            Some(coverage) if x < self.field.width() => coverage
+                .get(x + self.field.width() * y)
+                .cloned()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
 
-        if let Mode::Terminate = self.mode {
-            return None;
+    /// Get the percentage of non-space cells of the playfield which have been executed.
+    ///
+    /// Returns zero if the playfield only consists of spaces or coverage tracking is disabled.
+    pub fn coverage(&self) -> f64 {
+        let executed = self.executed_cells();
+        let mut total = 0;
+        let mut covered = 0;
+
+        for (y, line) in self.field.lines().enumerate() {
+            for (x, _) in line.iter().enumerate().filter(|(_, &c)| c != instr::SPACE) {
+                total += 1;
+
+                if executed.contains(&(x, y)) {
+                    covered += 1;
+                }
+            }
         }
 
-        self.nav.step();
+        if total > 0 {
+            100.0 * f64::from(covered) / f64::from(total)
+        } else {
+            0.0
+        }
+    }
 
-        Some(())
+    /// Set the observer that is notified about the execution.
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observer = Some(observer);
     }
-}
 
-impl distributions::Distribution<Direction> for distributions::Standard {
-    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Direction {
-        match rng.gen_range(0, 4) {
-            0 => Direction::Up,
-            1 => Direction::Down,
-            2 => Direction::Left,
-            _ => Direction::Right,
-        }
+    /// Enable or disable strict mode.
+    ///
+    /// In strict mode, the interpreter emits a [`Warning`] to the observer whenever a program
+    /// behaves suspiciously. Execution itself is not affected.
+    ///
+    /// [`Warning`]: enum.Warning.html
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::playfield::Playfield;
+    /// Set the semantics of division and modulo for negative operands.
+    pub fn set_modulo_mode(&mut self, modulo: ModuloMode) {
+        self.modulo = modulo;
+    }
 
-    fn test_program(field: &str, input: &str, output: &str, execution: Vec<(Mode, Stack)>) {
-        let reader = input.bytes().collect::<Vec<_>>();
-        let writer = Vec::new();
+    /// Set the number of steps between two clock checks in [`run_to_completion_until`].
+    ///
+    /// Reading the clock is comparatively expensive, so it is only checked every `interval`
+    /// steps. An interval of zero is treated as one.
+    ///
+    /// [`run_to_completion_until`]: #method.run_to_completion_until
+    pub fn set_deadline_check_interval(&mut self, interval: usize) {
+        self.deadline_interval = interval.max(1);
+    }
 
-        let playfield = Playfield::new(field);
-        let io = InputOutput::new(&reader[..], writer);
-        let mut interpreter = Interpreter::new(playfield, io);
+    /// Set the way division and modulo by zero are handled.
+    pub fn set_div_by_zero_policy(&mut self, policy: DivByZeroPolicy) {
+        self.div_by_zero = policy;
+    }
 
-        for (mode, stack) in execution {
-            assert_eq!(mode, interpreter.mode);
-            assert_eq!(stack, interpreter.stack);
+    /// Set the way illegal instructions are handled.
+    pub fn set_illegal_policy(&mut self, policy: IllegalPolicy) {
+        self.illegal_policy = policy;
+    }
 
-            interpreter.next();
+    /// Grow the playfield whenever `p` writes to a cell to the right of or below it.
+    ///
+    /// The playfield never grows beyond [`set_max_field_cells`] cells. Writes to negative
+    /// coordinates are always ignored.
+    ///
+    /// [`set_max_field_cells`]: #method.set_max_field_cells
+    pub fn set_auto_grow(&mut self, auto_grow: bool) {
+        self.auto_grow = auto_grow;
+    }
+
+    /// Set the maximum number of cells the playfield may grow to, which defaults to
+    /// [`DEFAULT_MAX_FIELD_CELLS`].
+    ///
+    /// [`DEFAULT_MAX_FIELD_CELLS`]: constant.DEFAULT_MAX_FIELD_CELLS.html
+    pub fn set_max_field_cells(&mut self, max_field_cells: usize) {
+        self.max_field_cells = max_field_cells;
+    }
+
+    /// Set the way a `p` which would grow the playfield beyond its limit is handled.
+    pub fn set_grow_policy(&mut self, policy: GrowPolicy) {
+        self.grow_policy = policy;
+    }
+
+    /// Set the way popping from an empty stack is handled.
+    pub fn set_empty_pop_policy(&mut self, policy: EmptyPopPolicy) {
+        self.empty_pop = policy;
+    }
+
+    /// Get the illegal instructions which have been skipped as `(x, y, byte)`.
+    ///
+    /// Each execution of an illegal instruction is recorded, see [`IllegalPolicy::Skip`].
+    ///
+    /// [`IllegalPolicy::Skip`]: enum.IllegalPolicy.html#variant.Skip
+    pub fn skipped_illegals(&self) -> &[(usize, usize, u8)] {
+        &self.skipped_illegals
+    }
+
+    fn warn(&mut self, warning: Warning) {
+        if let (true, Some(observer)) = (self.strict, &mut self.observer) {
+            observer.on_warning(warning);
         }
+    }
 
-        assert_eq!(output.bytes().collect::<Vec<_>>(), interpreter.io.writer);
+    /// Treat the given byte as a no-op instead of an illegal instruction.
+    ///
+    /// This allows annotating programs, e.g. with `;`. Bytes which are instructions in the
+    /// current dialect keep their meaning.
+    pub fn add_no_op(&mut self, c: u8) {
+        self.no_ops.insert(c);
     }
 
-    #[test]
-    fn interpret_digits() {
-        test_program(
-            "0123456789",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![0]),
-                (Mode::Execute, vec![0, 1]),
-                (Mode::Execute, vec![0, 1, 2]),
-                (Mode::Execute, vec![0, 1, 2, 3]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4, 5]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]),
-                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
-            ],
-        );
+    /// Execute the byte `from` as if it were the instruction `to`.
+    ///
+    /// Aliases are resolved when they are added, so aliasing `from` to a byte which is itself
+    /// an alias uses that alias' instruction, and existing aliases of `from` follow it to the
+    /// new instruction. An alias which would resolve back to `from` removes the alias of
+    /// `from` instead, so there are never any cycles. Aliases only apply outside of string
+    /// mode.
+    pub fn alias(&mut self, from: u8, to: u8) {
+        let to = self.resolve(to);
+
+        for target in self.aliases.values_mut() {
+            if *target == from {
+                *target = to;
+            }
+        }
+
+        self.aliases.insert(from, to);
+        self.aliases.retain(|from, to| from != to);
     }
 
-    #[test]
-    fn interpret_arithmetic() {
-        test_program(
-            "73+",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![10]),
-            ],
-        );
+    /// Always move into the given direction when executing a `?` at the given position.
+    ///
+    /// Any `?` elsewhere still picks a random direction. Forced directions are configuration
+    /// rather than execution state, so they are kept by [`restore`] and [`rewind`].
+    ///
+    /// [`restore`]: #method.restore
+    /// [`rewind`]: #method.rewind
+    pub fn force_random_at(&mut self, pos: (usize, usize), dir: Direction) {
+        self.forced_random.insert(pos, (dir, false));
+    }
 
-        test_program(
-            "73-",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![4]),
-            ],
-        );
+    /// Move into the given direction the next time a `?` at the given position is executed.
+    ///
+    /// The override is removed once it has been used. Restoring a snapshot taken before that
+    /// does not bring it back, so the `?` picks a random direction when it is executed again.
+    pub fn force_random_once_at(&mut self, pos: (usize, usize), dir: Direction) {
+        self.forced_random.insert(pos, (dir, true));
+    }
 
-        test_program(
-            "73*",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![21]),
-            ],
-        );
+    /// Remove the forced direction at the given position.
+    pub fn remove_forced_random(&mut self, pos: (usize, usize)) {
+        self.forced_random.remove(&pos);
+    }
 
-        test_program(
-            "73/",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![2]),
-            ],
-        );
+    /// Return the instruction the given byte is executed as outside of string mode.
+    ///
+    /// This is the instruction the byte is aliased to, or the byte itself if it has no alias.
+    pub fn resolve(&self, c: u8) -> u8 {
+        self.aliases.get(&c).copied().unwrap_or(c)
+    }
 
-        test_program(
-            "73%",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![1]),
-            ],
-        );
+    /// Remove the alias of the given byte.
+    pub fn remove_alias(&mut self, from: u8) {
+        self.aliases.remove(&from);
     }
 
-    #[test]
-    fn interpret_logic() {
-        test_program(
-            "0!",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![0]),
-                (Mode::Execute, vec![1]),
-            ],
-        );
+    /// Treat the given byte as an illegal instruction again.
+    pub fn remove_no_op(&mut self, c: u8) {
+        self.no_ops.remove(&c);
+    }
 
-        test_program(
-            "5!",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-                (Mode::Execute, vec![0]),
-            ],
-        );
+    /// Trap every execution of the given instruction.
+    ///
+    /// Whenever this instruction is about to be executed anywhere in the playfield, the step
+    /// returns [`StepOutcome::Trapped`] without executing it.
+    ///
+    /// [`StepOutcome::Trapped`]: enum.StepOutcome.html#variant.Trapped
+    pub fn add_trap(&mut self, instruction: u8) {
+        self.traps.insert(instruction);
+    }
 
-        test_program(
-            "73`",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 3]),
-                (Mode::Execute, vec![1]),
-            ],
-        );
+    /// Remove the trap for the given instruction.
+    pub fn remove_trap(&mut self, instruction: u8) {
+        self.traps.remove(&instruction);
+    }
 
-        test_program(
-            "45`",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![4]),
-                (Mode::Execute, vec![4, 5]),
-                (Mode::Execute, vec![0]),
-            ],
-        );
+    /// Protect the region with the top left corner at `pos` and the given size from `p`.
+    ///
+    /// A `p` which would write into any protected region is not executed and returns a
+    /// [`StepError::ProtectedWrite`] instead.
+    ///
+    /// [`StepError::ProtectedWrite`]: enum.StepError.html#variant.ProtectedWrite
+    pub fn protect_region(&mut self, pos: (usize, usize), size: (usize, usize)) {
+        self.protected.push((pos, size));
     }
 
-    #[test]
-    fn interpret_direction() {
-        test_program(
-            "v\n3\n>4",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![3]),
-                (Mode::Execute, vec![3]),
-                (Mode::Execute, vec![3, 4]),
-            ],
-        );
+    fn is_protected(&self, (x, y): (usize, usize)) -> bool {
+        self.protected
+            .iter()
+            .any(|&((px, py), (w, h))| (px..px + w).contains(&x) && (py..py + h).contains(&y))
+    }
 
-        test_program(
-            "<@^\n  @\n  5",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-            ],
+    /// Add a breakpoint at the given position.
+    ///
+    /// The breakpoint is hit whenever the navigator arrives at this position.
+    pub fn add_breakpoint(&mut self, pos: (usize, usize)) {
+        self.breakpoints.insert(
+            pos,
+            Breakpoint {
+                count: None,
+                hits: 0,
+            },
         );
+    }
 
-        test_program(
-            "?5@5\n5\n@\n5",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-            ],
-        );
+    /// Add a breakpoint at the given position which is only hit on the `n`-th arrival.
+    pub fn add_breakpoint_with_count(&mut self, pos: (usize, usize), n: usize) {
+        let count = Some(n);
+
+        self.breakpoints.insert(pos, Breakpoint { count, hits: 0 });
     }
 
-    #[test]
-    fn interpret_controlflow() {
-        test_program(
-            "0_5",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![0]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-            ],
-        );
+    /// Remove the breakpoint at the given position.
+    pub fn remove_breakpoint(&mut self, pos: (usize, usize)) {
+        self.breakpoints.remove(&pos);
+    }
 
-        test_program(
+    /// List all configured breakpoints.
+    ///
+    /// Positional breakpoints come first ordered by position, followed by instruction
+    /// breakpoints ordered by instruction.
+    pub fn breakpoints(&self) -> Vec<BreakpointInfo> {
+        let mut breakpoints: Vec<BreakpointInfo> = self
+            .breakpoints
+            .iter()
+            .map(|(&pos, breakpoint)| BreakpointInfo::Position {
+                pos,
+                count: breakpoint.count,
+                hits: breakpoint.hits,
+            })
+            .chain(self.traps.iter().map(|&i| BreakpointInfo::Instruction(i)))
+            .collect();
+
+        breakpoints.sort();
+        breakpoints
+    }
+
+    fn is_trapped(&self, val: u8) -> bool {
+        if self.resume {
+            return false;
+        }
+
+        // Hits are only counted once the step has been executed, so this arrival is the next hit
+        if let Some(breakpoint) = self.breakpoints.get(&self.nav.pos()) {
+            match breakpoint.count {
+                Some(n) if n != breakpoint.hits + 1 => (),
+                _ => return true,
+            }
+        }
+
+        self.mode == Mode::Execute && self.traps.contains(&val)
+    }
+
+    /// Execute a single step of the program.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step fails, e.g. on an illegal instruction with [`IllegalPolicy::Fail`].
+    /// Use [`try_step`] to handle such errors instead.
+    ///
+    /// [`IllegalPolicy::Fail`]: enum.IllegalPolicy.html#variant.Fail
+    /// [`try_step`]: #method.try_step
+    pub fn step(&mut self) -> StepOutcome {
+        match self.try_step() {
+            Ok(outcome) => outcome,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Execute a single step like [`Iterator::next`], but return an error instead of panicking.
+    ///
+    /// Returns `None` once the program has terminated. The failing instruction is not executed,
+    /// see [`try_step`].
+    ///
+    /// [`Iterator::next`]: #method.next
+    /// [`try_step`]: #method.try_step
+    pub fn try_next(&mut self) -> Option<Result<(), StepError>> {
+        match self.try_step() {
+            Ok(StepOutcome::Continue) | Ok(StepOutcome::Trapped(_)) => Some(Ok(())),
+            Ok(StepOutcome::Terminated) | Ok(StepOutcome::TimedOut) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// Execute a single step of the program and return a record of it.
+    ///
+    /// This gathers everything observers would learn about the step in a single call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step fails, e.g. on an illegal instruction with [`IllegalPolicy::Fail`].
+    /// Use [`try_step`] to handle such errors instead.
+    ///
+    /// [`IllegalPolicy::Fail`]: enum.IllegalPolicy.html#variant.Fail
+    /// [`try_step`]: #method.try_step
+    pub fn step_record(&mut self) -> StepRecord {
+        let pos = self.nav.pos();
+        let instruction = self.field[pos];
+        let mode_before = self.mode;
+
+        let outcome = self.step();
+
+        StepRecord {
+            pos,
+            instruction,
+            mode_before,
+            mode_after: self.mode,
+            stack: self.stack.clone(),
+            effect: self.last_effect,
+            outcome,
+        }
+    }
+
+    /// Execute a single step of the program, returning an error for illegal instructions,
+    /// writes into protected regions and, if enabled, pops from an empty stack and writes
+    /// beyond the limit of an auto-growing playfield.
+    ///
+    /// The failing instruction is not executed, so the interpreter stays at its position. If
+    /// illegal instructions are skipped, no error is returned for them, see
+    /// [`set_illegal_policy`].
+    ///
+    /// [`set_illegal_policy`]: #method.set_illegal_policy
+    pub fn try_step(&mut self) -> Result<StepOutcome, StepError> {
+        self.last_effect = StepEffect::None;
+
+        if let Mode::Terminate = self.mode {
+            return Ok(StepOutcome::Terminated);
+        }
+
+        let val = self.field[self.nav.pos()];
+
+        if self.is_trapped(val) {
+            self.resume = true;
+
+            return Ok(StepOutcome::Trapped(self.nav.pos()));
+        }
+
+        self.resume = false;
+
+        let val = match self.mode {
+            Mode::Execute => self.resolve(val),
+            _ => val,
+        };
+        let illegal = self.mode == Mode::Execute
+            && (self.instruction_at(self.nav.pos()).is_none() || !self.is_legal(val));
+
+        if illegal {
+            let (x, y) = self.nav.pos();
+
+            match self.illegal_policy {
+                IllegalPolicy::Fail => {
+                    return Err(StepError::IllegalInstruction {
+                        pos: (x, y),
+                        instruction: val,
+                    })
+                }
+                IllegalPolicy::Skip => self.skipped_illegals.push((x, y, val)),
+            }
+        }
+
+        if self.mode == Mode::Execute
+            && self.div_by_zero == DivByZeroPolicy::Fail
+            && matches!(val, instr::DIVIDE | instr::MODULO)
+            && self.has_operands(2)
+            && self.stack.last().copied().unwrap_or(0) == 0
+        {
+            return Err(StepError::DivideByZero {
+                pos: self.nav.pos(),
+                instruction: val,
+            });
+        }
+
+        if self.mode == Mode::Execute && val == instr::PUT && self.has_operands(3) {
+            let top = |i: usize| {
+                let len = self.stack.len();
+
+                if i < len {
+                    self.stack[len - 1 - i]
+                } else {
+                    0
+                }
+            };
+
+            let (x, y) = (top(1), top(0));
+
+            // Protected regions may lie beyond the playfield, which auto-growing can reach
+            if let (Ok(tx), Ok(ty)) = (usize::try_from(x), usize::try_from(y)) {
+                if self.is_protected((tx, ty)) {
+                    return Err(StepError::ProtectedWrite {
+                        pos: self.nav.pos(),
+                        target: (tx, ty),
+                    });
+                }
+            }
+
+            if self.field_pos(x, y).is_none()
+                && self.grow_policy == GrowPolicy::Fail
+                && self
+                    .grow_dimensions(x, y)
+                    .is_some_and(|dim| !self.within_field_limit(dim))
+            {
+                return Err(StepError::FieldLimitExceeded {
+                    pos: self.nav.pos(),
+                    target: (x, y),
+                });
+            }
+        }
+
+        // The snapshot, the coverage and breakpoint hits are only recorded once the step has
+        // succeeded
+        let pos = self.nav.pos();
+        let snapshot = if self.history_depth > 0 {
+            Some(self.snapshot())
+        } else {
+            None
+        };
+
+        // Befunge-93 only knows the four cardinal directions
+        debug_assert!(self.dialect != Dialect::Befunge93 || self.nav.dir().is_some());
+
+        let from = self.mode;
+
+        self.mode = match self.mode {
+            Mode::Execute if illegal => Mode::Execute,
+            Mode::Execute => match self.execute_step(val) {
+                Some(mode) => mode,
+                None => {
+                    return Err(StepError::StackUnderflow {
+                        pos,
+                        instruction: val,
+                    })
+                }
+            },
+            Mode::Parse => self.parse_step(val),
+            Mode::Terminate => Mode::Terminate,
+        };
+
+        if let Some(snapshot) = snapshot {
+            if self.history.len() == self.history_depth {
+                self.history.pop_front();
+            }
+
+            self.history.push_back(snapshot);
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            coverage[pos.0 + self.field.width() * pos.1] += 1;
+        }
+
+        if let Some(breakpoint) = self.breakpoints.get_mut(&pos) {
+            breakpoint.hits += 1;
+        }
+
+        if let (true, Some(observer)) = (from != self.mode, &mut self.observer) {
+            observer.on_mode_change(from, self.mode);
+        }
+
+        self.steps += 1;
+        self.max_stack_depth = self.max_stack_depth.max(self.stack.len());
+
+        if let Mode::Terminate = self.mode {
+            self.io.flush_pending();
+
+            return Ok(StepOutcome::Terminated);
+        }
+
+        if let Some(dir) = self.nav.dir() {
+            self.movement[dir.as_u8() as usize] += 1;
+        }
+
+        self.nav.step();
+
+        Ok(StepOutcome::Continue)
+    }
+
+    /// Execute steps until the program terminates or a trap is hit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a step fails like [`step`]. Use [`run_with_limit`] to handle errors instead.
+    ///
+    /// [`step`]: #method.step
+    /// [`run_with_limit`]: #method.run_with_limit
+    pub fn run_to_completion(&mut self) -> StepOutcome {
+        loop {
+            match self.step() {
+                StepOutcome::Continue => (),
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Execute steps until the program terminates, a trap is hit or the deadline passes.
+    ///
+    /// The deadline is checked before the first step and then every few steps as configured
+    /// with [`set_deadline_check_interval`], so it may be overrun by up to that many steps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a step fails like [`step`], e.g. on an illegal instruction. Use [`try_step`]
+    /// to handle errors instead.
+    ///
+    /// [`set_deadline_check_interval`]: #method.set_deadline_check_interval
+    /// [`step`]: #method.step
+    /// [`try_step`]: #method.try_step
+    pub fn run_to_completion_until(&mut self, deadline: Instant) -> StepOutcome {
+        let mut steps = 0;
+
+        loop {
+            if steps % self.deadline_interval == 0 && Instant::now() >= deadline {
+                return StepOutcome::TimedOut;
+            }
+
+            match self.step() {
+                StepOutcome::Continue => steps += 1,
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Execute at most `max_steps` steps until the program terminates or a trap is hit.
+    ///
+    /// Returns [`StepOutcome::Continue`] if the program is still running after `max_steps`
+    /// steps and an error if a step fails, see [`try_step`].
+    ///
+    /// [`StepOutcome::Continue`]: enum.StepOutcome.html#variant.Continue
+    /// [`try_step`]: #method.try_step
+    pub fn run_with_limit(&mut self, max_steps: usize) -> Result<StepOutcome, StepError> {
+        for _ in 0..max_steps {
+            match self.try_step()? {
+                StepOutcome::Continue => (),
+                outcome => return Ok(outcome),
+            }
+        }
+
+        Ok(StepOutcome::Continue)
+    }
+
+    /// Execute steps until the row of the instruction pointer changes.
+    ///
+    /// Stops early if the program terminates, a trap is hit or [`STEP_UNTIL_LIMIT`] steps have
+    /// been executed, e.g. for programs which never move vertically.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a step fails like [`step`], e.g. on an illegal instruction. Use [`try_step`]
+    /// to handle errors instead.
+    ///
+    /// [`STEP_UNTIL_LIMIT`]: constant.STEP_UNTIL_LIMIT.html
+    /// [`step`]: #method.step
+    /// [`try_step`]: #method.try_step
+    pub fn step_until_row_change(&mut self) -> StepOutcome {
+        let (_, y) = self.nav.pos();
+
+        self.step_until(|pos| pos.1 != y)
+    }
+
+    /// Execute steps until the column of the instruction pointer changes.
+    ///
+    /// Stops early and panics like [`step_until_row_change`].
+    ///
+    /// [`step_until_row_change`]: #method.step_until_row_change
+    pub fn step_until_col_change(&mut self) -> StepOutcome {
+        let (x, _) = self.nav.pos();
+
+        self.step_until(|pos| pos.0 != x)
+    }
+
+    /// Execute steps until `done` holds for the new position, panicking if a step fails.
+    fn step_until<F: Fn((usize, usize)) -> bool>(&mut self, done: F) -> StepOutcome {
+        for _ in 0..STEP_UNTIL_LIMIT {
+            match self.step() {
+                StepOutcome::Continue if done(self.nav.pos()) => break,
+                StepOutcome::Continue => (),
+                outcome => return outcome,
+            }
+        }
+
+        StepOutcome::Continue
+    }
+
+    /// Execute the program and iterate over the executed basic blocks.
+    pub fn basic_blocks(&mut self) -> BasicBlocks<'_, R, W> {
+        BasicBlocks::new(self)
+    }
+
+    /// Get the side effect of the most recent step.
+    pub fn last_effect(&self) -> StepEffect {
+        self.last_effect
+    }
+
+    /// Get the name of the instruction under the instruction pointer.
+    ///
+    /// Returns "space" for spaces and "illegal" for bytes which are no instruction in the
+    /// current dialect. The cell is looked up even while parsing a string.
+    pub fn current_instruction_name(&self) -> &'static str {
+        let val = self.field[self.nav.pos()];
+
+        match InstructionInfo::lookup(val, self.dialect) {
+            Some(info) => info.name,
+            None if val == instr::SPACE => "space",
+            None if self.no_ops.contains(&val) => "no-op",
+            None => "illegal",
+        }
+    }
+
+    /// Check whether the next step reads input, i.e. the instruction pointer is on `&` or `~`
+    /// while executing instructions.
+    pub fn awaits_input(&self) -> bool {
+        match self.mode {
+            Mode::Execute => matches!(
+                self.field[self.nav.pos()],
+                instr::INPUT_INTEGER | instr::INPUT_CHARACTER
+            ),
+            _ => false,
+        }
+    }
+
+    /// Predict the direction a branch instruction under the instruction pointer takes.
+    ///
+    /// The direction is computed from the current stack without executing the instruction.
+    /// For `#`, the direction does not change. Returns `None` for `?`, which is
+    /// nondeterministic unless its direction is forced, for all other instructions and while
+    /// parsing a string.
+    pub fn predict_branch(&self) -> Option<Direction> {
+        if self.mode != Mode::Execute {
+            return None;
+        }
+
+        let top = self.stack.last().cloned().unwrap_or(0);
+
+        match self.field[self.nav.pos()] {
+            instr::HORIZONTAL_IF if top == 0 => Some(Direction::Right),
+            instr::HORIZONTAL_IF => Some(Direction::Left),
+            instr::VERTICAL_IF if top == 0 => Some(Direction::Down),
+            instr::VERTICAL_IF => Some(Direction::Up),
+            instr::BRIDGE => self.nav.dir(),
+            instr::RANDOM => self.forced_random.get(&self.nav.pos()).map(|&(dir, _)| dir),
+            _ => None,
+        }
+    }
+
+    /// Check whether a `@` is statically reachable from the current position and delta.
+    ///
+    /// All directions of a branch are considered taken, regardless of the stack. Since `p` may
+    /// alter the control flow, reaching a `p` counts as reaching an exit, and so does `x` in
+    /// Funge-98, whose delta comes from the stack. A terminated program can always exit.
+    ///
+    /// Everything else which stops execution counts as an exit as well: traps, breakpoints,
+    /// illegal instructions with [`IllegalPolicy::Fail`] and `/` and `%` with
+    /// [`DivByZeroPolicy::Fail`]. Popping from an empty stack is not considered.
+    ///
+    /// [`IllegalPolicy::Fail`]: enum.IllegalPolicy.html#variant.Fail
+    /// [`DivByZeroPolicy::Fail`]: enum.DivByZeroPolicy.html#variant.Fail
+    pub fn can_reach_exit(&self) -> bool {
+        let funge98 = self.dialect == Dialect::Funge98;
+        let reflects = self.div_by_zero == DivByZeroPolicy::Reflect;
+        let divides_fail = self.div_by_zero == DivByZeroPolicy::Fail;
+        let illegal_fails = self.illegal_policy == IllegalPolicy::Fail;
+
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        // A trap at the current position does not stop a resuming interpreter again
+        queue.push_back((self.nav.clone(), self.mode, !self.resume));
+
+        while let Some((nav, mode, traps)) = queue.pop_front() {
+            let pos = nav.pos();
+
+            if traps && !visited.insert((pos, nav.delta(), mode)) {
+                continue;
+            }
+
+            let val = self.field[pos];
+            let (dx, dy) = nav.delta();
+
+            if traps
+                && (self.breakpoints.contains_key(&pos)
+                    || mode == Mode::Execute && self.traps.contains(&val))
+            {
+                return true;
+            }
+
+            let val = self.resolve(val);
+            let legal = self.instruction_at(pos).is_some() && self.is_legal(val);
+            let mut next = Vec::new();
+
+            match mode {
+                Mode::Terminate => return true,
+                Mode::Parse if self.field[pos] == instr::STRING_MODE => {
+                    next.push((dx, dy, Mode::Execute))
+                }
+                Mode::Parse => next.push((dx, dy, Mode::Parse)),
+                Mode::Execute if !legal && illegal_fails => return true,
+                Mode::Execute if !legal => next.push((dx, dy, mode)),
+                Mode::Execute => match val {
+                    instr::TERMINATE | instr::PUT => return true,
+                    instr::DIVIDE | instr::MODULO if divides_fail => return true,
+                    instr::ABSOLUTE_DELTA if funge98 => return true,
+                    instr::RIGHT => next.push((1, 0, mode)),
+                    instr::LEFT => next.push((-1, 0, mode)),
+                    instr::UP => next.push((0, -1, mode)),
+                    instr::DOWN => next.push((0, 1, mode)),
+                    instr::RANDOM => match self.forced_random.get(&nav.pos()) {
+                        Some(&(dir, false)) => next.push((dir.delta().0, dir.delta().1, mode)),
+                        _ => {
+                            next.extend([(1, 0, mode), (-1, 0, mode), (0, -1, mode), (0, 1, mode)])
+                        }
+                    },
+                    instr::HORIZONTAL_IF => next.extend([(1, 0, mode), (-1, 0, mode)]),
+                    instr::VERTICAL_IF => next.extend([(0, 1, mode), (0, -1, mode)]),
+                    instr::STRING_MODE => next.push((dx, dy, Mode::Parse)),
+                    instr::BRIDGE => {
+                        let mut nav = nav.clone();
+
+                        nav.step();
+                        nav.step();
+                        queue.push_back((nav, mode, true));
+                    }
+                    instr::DIVIDE | instr::MODULO if reflects => {
+                        next.extend([(dx, dy, mode), (dx.wrapping_neg(), dy.wrapping_neg(), mode)])
+                    }
+                    instr::TURN_LEFT if funge98 => next.push((dy, dx.wrapping_neg(), mode)),
+                    instr::TURN_RIGHT if funge98 => next.push((dy.wrapping_neg(), dx, mode)),
+                    instr::REFLECT if funge98 => {
+                        next.push((dx.wrapping_neg(), dy.wrapping_neg(), mode))
+                    }
+                    instr::COMPARE if funge98 => next.extend([
+                        (dx, dy, mode),
+                        (dy, dx.wrapping_neg(), mode),
+                        (dy.wrapping_neg(), dx, mode),
+                    ]),
+                    _ => next.push((dx, dy, mode)),
+                },
+            }
+
+            for (dx, dy, mode) in next {
+                let mut nav = nav.clone();
+
+                nav.set_delta((dx, dy));
+                nav.step();
+                queue.push_back((nav, mode, true));
+            }
+        }
+
+        false
+    }
+
+    /// Return the instruction in the cell at the given position.
+    ///
+    /// Returns `None` for wide cells holding a value beyond a byte, which are no instructions.
+    fn instruction_at(&self, pos: (usize, usize)) -> Option<u8> {
+        match self.field.value(pos) {
+            0..=255 => Some(self.field[pos]),
+            _ => None,
+        }
+    }
+
+    fn is_legal(&self, c: u8) -> bool {
+        instruction::is_instruction(c, self.dialect)
+            || c == instr::SPACE
+            || !self.no_ops.is_empty() && self.no_ops.contains(&c)
+    }
+
+    /// Return whether popping `n` values succeeds under the [`EmptyPopPolicy`].
+    ///
+    /// Prechecks use this so that a missing operand is reported as a stack underflow.
+    ///
+    /// [`EmptyPopPolicy`]: enum.EmptyPopPolicy.html
+    fn has_operands(&self, n: usize) -> bool {
+        self.empty_pop != EmptyPopPolicy::Error || self.stack.len() >= n
+    }
+
+    /// Pop `N` values from the stack, starting with the value on top.
+    ///
+    /// Missing values are zero, unless the policy is [`EmptyPopPolicy::Error`]: then `None` is
+    /// returned and nothing is popped if the stack holds fewer than `N` values.
+    ///
+    /// [`EmptyPopPolicy::Error`]: enum.EmptyPopPolicy.html#variant.Error
+    fn pop<const N: usize>(&mut self) -> Option<[i64; N]> {
+        if self.empty_pop == EmptyPopPolicy::Error && self.stack.len() < N {
+            return None;
+        }
+
+        Some(array::from_fn(|_| stack_ops::pop(&mut self.stack)))
+    }
+
+    /// Apply the given stack operation, which pops up to `N` values.
+    ///
+    /// The operands are checked by popping them and pushing them back before the operation.
+    fn stack_op<const N: usize>(&mut self, op: fn(&mut Stack)) -> Option<()> {
+        let values: [i64; N] = self.pop()?;
+
+        self.stack.extend(values.iter().rev());
+        op(&mut self.stack);
+
+        Some(())
+    }
+
+    /// Return the dimensions the playfield needs to grow to for a write to `(x, y)`.
+    ///
+    /// Returns `None` if auto-growing is disabled or the position is negative.
+    fn grow_dimensions(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        if !self.auto_grow {
+            return None;
+        }
+
+        let (width, height) = self.field.dimensions();
+        let x = usize::try_from(x).ok()?.checked_add(1)?;
+        let y = usize::try_from(y).ok()?.checked_add(1)?;
+
+        Some((width.max(x), height.max(y)))
+    }
+
+    fn within_field_limit(&self, (width, height): (usize, usize)) -> bool {
+        width
+            .checked_mul(height)
+            .is_some_and(|cells| cells <= self.max_field_cells)
+    }
+
+    fn field_pos(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let (width, height) = self.field.dimensions();
+
+        if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    fn execute_step(&mut self, c: u8) -> Option<Mode> {
+        let funge98 = self.dialect == Dialect::Funge98;
+
+        match c {
+            // Push this number on the stack
+            instr::PUSH_0..=instr::PUSH_9 => self.stack.push(i64::from(c - instr::PUSH_0)),
+
+            // Addition: Pop a and b, then push a+b
+            instr::ADD => {
+                let [a, b] = self.pop()?;
+
+                self.stack.push(a.wrapping_add(b));
+            }
+
+            // Subtraction: Pop a and b, then push b-a
+            instr::SUBTRACT => {
+                let [a, b] = self.pop()?;
+
+                self.stack.push(b.wrapping_sub(a));
+            }
+
+            // Multiplication: Pop a and b, then push a*b
+            instr::MULTIPLY => {
+                let [a, b] = self.pop()?;
+
+                self.stack.push(a.wrapping_mul(b));
+            }
+
+            // Integer division: Pop a and b, then push b/a, rounded towards 0 unless Euclidean
+            // division is used. Division by zero follows the DivByZeroPolicy.
+            instr::DIVIDE => {
+                let [a, b] = self.pop()?;
+
+                if a == 0 && self.div_by_zero == DivByZeroPolicy::Reflect {
+                    self.reflect();
+
+                    return Some(Mode::Execute);
+                }
+
+                self.stack.push(match self.modulo {
+                    _ if a == 0 => 0,
+                    ModuloMode::Rust => b.wrapping_div(a),
+                    ModuloMode::Euclidean => b.wrapping_div_euclid(a),
+                });
+            }
+
+            // Modulo: Pop a and b, then push the remainder of the integer division of b/a. Modulo
+            // zero follows the DivByZeroPolicy.
+            instr::MODULO => {
+                let [a, b] = self.pop()?;
+
+                if a == 0 && self.div_by_zero == DivByZeroPolicy::Reflect {
+                    self.reflect();
+
+                    return Some(Mode::Execute);
+                }
+
+                self.stack.push(match self.modulo {
+                    _ if a == 0 => 0,
+                    ModuloMode::Rust => b.wrapping_rem(a),
+                    ModuloMode::Euclidean => b.wrapping_rem_euclid(a),
+                });
+            }
+
+            // Logical NOT: Pop a value. If the value is zero, push 1; otherwise, push zero.
+            instr::NOT => {
+                let [v] = self.pop()?;
+
+                if v == 0 {
+                    self.stack.push(1)
+                } else {
+                    self.stack.push(0)
+                }
+            }
+
+            // Greater than: Pop a and b, then push 1 if b>a, otherwise zero.
+            instr::GREATER => {
+                let [a, b] = self.pop()?;
+
+                if b > a {
+                    self.stack.push(1)
+                } else {
+                    self.stack.push(0)
+                }
+            }
+
+            // Start moving right
+            instr::RIGHT => self.nav.turn(Direction::Right),
+
+            // Start moving left
+            instr::LEFT => self.nav.turn(Direction::Left),
+
+            // Start moving up
+            instr::UP => self.nav.turn(Direction::Up),
+
+            // Start moving down
+            instr::DOWN => self.nav.turn(Direction::Down),
+
+            // Start moving in a random cardinal direction, unless it is forced for this position
+            instr::RANDOM => {
+                let pos = self.nav.pos();
+                let dir = match self.forced_random.get(&pos) {
+                    Some(&(dir, true)) => {
+                        self.forced_random.remove(&pos);
+                        dir
+                    }
+                    Some(&(dir, false)) => dir,
+                    None => rand::random(),
+                };
+
+                self.nav.turn(dir)
+            }
+
+            // Pop a value; move right if value=0, left otherwise
+            instr::HORIZONTAL_IF => {
+                let [v] = self.pop()?;
+
+                if v == 0 {
+                    self.nav.turn(Direction::Right)
+                } else {
+                    self.nav.turn(Direction::Left)
+                }
+            }
+
+            // Pop a value; move down if value=0, up otherwise
+            instr::VERTICAL_IF => {
+                let [v] = self.pop()?;
+
+                if v == 0 {
+                    self.nav.turn(Direction::Down)
+                } else {
+                    self.nav.turn(Direction::Up)
+                }
+            }
+
+            // Start string mode: push each character's ASCII value all the way up to the next "
+            instr::STRING_MODE => {
+                self.parse_len = 0;
+
+                return Some(Mode::Parse);
+            }
+
+            // Duplicate value on top of the stack
+            instr::DUPLICATE => self.stack_op::<1>(stack_ops::dup)?,
+
+            // Swap two values on top of the stack
+            instr::SWAP => self.stack_op::<2>(stack_ops::swap)?,
+
+            // Pop value from the stack and discard it
+            instr::DISCARD => self.stack_op::<1>(stack_ops::drop)?,
+
+            // Pop value and output as an integer followed by a space
+            instr::OUTPUT_INTEGER => {
+                let [v] = self.pop()?;
+
+                self.io.write_int(v);
+                self.last_effect = StepEffect::OutputWritten;
+            }
+
+            // Pop value and output as ASCII character
+            instr::OUTPUT_CHARACTER => {
+                let [v] = self.pop()?;
+
+                self.io.write_ascii(v);
+                self.last_effect = StepEffect::OutputWritten;
+            }
+
+            // Bridge: Skip next cell
+            //
+            // The cell is skipped along the delta, so with a delta of (2,0) the instruction
+            // pointer moves four cells in total.
+            instr::BRIDGE => self.nav.step(),
+
+            // A "put" call (a way to store a value for later use).
+            //
+            // Pop y, x, and v, then change the character at (x,y) in the program to the character
+            // with ASCII value v. Byte cells only hold bytes, so v is truncated to its lowest 8
+            // bits, e.g. 300 is stored as 44 and -1 as 255. Wide cells store v unchanged.
+            // Positions outside of the playfield are ignored unless auto-growing is enabled, which
+            // grows the playfield up to its limit. Negative positions are always ignored.
+            instr::PUT => {
+                let [y, x, v] = self.pop()?;
+
+                let old = self.field.dimensions();
+                let pos = if self.auto_grow || self.field_pos(x, y).is_some() {
+                    self.field.put(x, y, v, self.max_field_cells)
+                } else {
+                    None
+                };
+
+                if let Some(pos) = pos {
+                    if self.field.dimensions() != old {
+                        self.update_dimensions(old);
+                    }
+
+                    if self.field.cell_type() == CellType::Byte && !(0..=255).contains(&v) {
+                        self.warn(Warning::TruncatedPut { pos, value: v });
+                    }
+
+                    self.last_effect = StepEffect::FieldModified;
+
+                    if let WrapMode::LaheySpace = self.nav.wrap_mode() {
+                        self.set_wrap_mode(WrapMode::LaheySpace);
+                    }
+                }
+            }
+
+            // A "get" call (a way to retrieve data in storage).
+            //
+            // Pop y and x, then push ASCII value of the character at that position in the program.
+            // Positions outside of the playfield push zero, while padding cells inside of it
+            // push their PaddingValue.
+            instr::GET => {
+                let [y, x] = self.pop()?;
+                let v = self.field_pos(x, y).map_or(0, |pos| self.field.value(pos));
+
+                self.stack.push(v)
+            }
+
+            // Ask user for a number and push it
+            // Invalid input and the end of the input push zero
+            instr::INPUT_INTEGER => {
+                let val = self.io.read_int();
+
+                self.push_input(val, 0);
+            }
+
+            // Ask user for a character and push its ASCII value
+            // The end of the input pushes -1
+            instr::INPUT_CHARACTER => {
+                let val = self.io.read_ascii();
+
+                self.push_input(val, -1);
+            }
+
+            // End program
+            instr::TERMINATE => return Some(Mode::Terminate),
+
+            // No-op. Does nothing
+            instr::SPACE => (),
+
+            // Funge-98: Push the hexadecimal number on the stack
+            instr::PUSH_10..=instr::PUSH_15 if funge98 => {
+                self.stack.push(i64::from(c - instr::PUSH_10) + 10)
+            }
+
+            // Funge-98: Clear the stack
+            instr::CLEAR if funge98 => stack_ops::clear(&mut self.stack),
+
+            // Funge-98: Turn left
+            instr::TURN_LEFT if funge98 => self.turn_left(),
+
+            // Funge-98: Turn right
+            instr::TURN_RIGHT if funge98 => self.turn_right(),
+
+            // Funge-98: Reverse the delta
+            instr::REFLECT if funge98 => self.reflect(),
+
+            // Funge-98: Pop dy and dx, then set the delta to (dx, dy)
+            instr::ABSOLUTE_DELTA if funge98 => {
+                let [dy, dx] = self.pop()?;
+
+                self.nav.set_delta((dx, dy))
+            }
+
+            // Funge-98: Pop b and a; turn left if a<b, turn right if a>b
+            instr::COMPARE if funge98 => {
+                let [b, a] = self.pop()?;
+
+                if a < b {
+                    self.turn_left()
+                } else if a > b {
+                    self.turn_right()
+                }
+            }
+
+            // Funge-98: No-op. Does nothing
+            instr::NO_OP if funge98 => (),
+
+            // Custom no-ops
+            _ if self.no_ops.contains(&c) => (),
+
+            // Illegal characters are rejected before execution
+            _ => unreachable!("Illegal character: {}", c as char),
+        }
+
+        Some(Mode::Execute)
+    }
+
+    fn push_input(&mut self, val: Option<i64>, default: i64) {
+        match val {
+            Some(val) => {
+                self.stack.push(val);
+                self.last_effect = StepEffect::InputRead;
+            }
+            None => {
+                self.stack.push(default);
+                self.last_effect = StepEffect::InputDefaulted;
+
+                if let Some(observer) = &mut self.observer {
+                    observer.on_input_defaulted(self.nav.pos());
+                }
+            }
+        }
+    }
+
+    fn turn_left(&mut self) {
+        let (dx, dy) = self.nav.delta();
+
+        self.nav.set_delta((dy, dx.wrapping_neg()))
+    }
+
+    fn turn_right(&mut self) {
+        let (dx, dy) = self.nav.delta();
+
+        self.nav.set_delta((dy.wrapping_neg(), dx))
+    }
+
+    fn reflect(&mut self) {
+        let (dx, dy) = self.nav.delta();
+
+        self.nav.set_delta((dx.wrapping_neg(), dy.wrapping_neg()))
+    }
+
+    fn parse_step(&mut self, c: u8) -> Mode {
+        if let instr::STRING_MODE = c {
+            return Mode::Execute;
+        }
+
+        self.stack.push(i64::from(c));
+        self.parse_len += 1;
+
+        Mode::Parse
+    }
+}
+
+impl<R, W> Interpreter<R, W>
+where
+    R: BufRead + Extend<u8>,
+    W: Write,
+{
+    /// Execute a single step, supplying input on demand.
+    ///
+    /// If the next step reads input, the given input is appended to the reader before it is
+    /// executed. Otherwise, the input is ignored. This allows a synchronous driver to provide
+    /// input exactly when it is needed, e.g. with a `VecDeque<u8>` as the reader.
+    pub fn feed_and_step(&mut self, input: Option<&[u8]>) -> StepOutcome {
+        if let Some(input) = input {
+            if self.awaits_input() {
+                self.io.reader_mut().extend(input.iter().cloned());
+            }
+        }
+
+        self.step()
+    }
+}
+
+impl<T, W> Interpreter<io::Cursor<T>, W>
+where
+    T: AsRef<[u8]>,
+    W: Write + Default,
+{
+    /// Rewind the input to its start and discard all output.
+    ///
+    /// Together with restoring a [`snapshot`] taken before the run, this allows running a program
+    /// again with the same input.
+    ///
+    /// [`snapshot`]: #method.snapshot
+    pub fn reset_io(&mut self) {
+        self.io.reader.set_position(0);
+        self.io.writer = W::default();
+        self.io.pending.clear();
+    }
+}
+
+impl<R, W> Interpreter<R, W>
+where
+    R: BufRead,
+    W: Write + AsRef<[u8]>,
+{
+    /// Produce a multi-line report of the complete execution state.
+    ///
+    /// The report contains the playfield with the current position marked, the stack, the
+    /// position and direction of the navigator, the mode, the number of steps and the output
+    /// written so far. This is only available for writers which keep their output in memory.
+    pub fn state_report(&self) -> String {
+        let direction = match self.nav.dir() {
+            Some(dir) => format!("{:?}", dir),
+            None => format!("{:?}", self.nav.delta()),
+        };
+
+        format!(
+            "Playfield:\n{}Stack: {}\nPosition: {:?}\nDirection: {}\nMode: {:?}\nSteps: {}\nOutput: {:?}\n",
+            self.field.render_with_cursor(self.nav.pos()),
+            format_stack(&self.stack),
+            self.nav.pos(),
+            direction,
+            self.mode,
+            self.steps,
+            String::from_utf8_lossy(self.io.writer().as_ref()),
+        )
+    }
+
+    /// Parse the output written so far as whitespace-separated integers.
+    ///
+    /// This is meant for programs which only write integers with `.`. Tokens which are no
+    /// integers, e.g. characters written with `,`, are skipped.
+    pub fn output_as_ints(&self) -> Vec<i64> {
+        String::from_utf8_lossy(self.io.writer().as_ref())
+            .split_whitespace()
+            .filter_map(|token| token.parse().ok())
+            .collect()
+    }
+}
+
+impl<R, W> Iterator for Interpreter<R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    type Item = ();
+
+    /// Execute a single step of the program.
+    ///
+    /// Returns `None` once the program has terminated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step fails, e.g. on an illegal instruction with [`IllegalPolicy::Fail`].
+    /// Use [`try_next`] to handle such errors instead.
+    ///
+    /// [`IllegalPolicy::Fail`]: enum.IllegalPolicy.html#variant.Fail
+    /// [`try_next`]: struct.Interpreter.html#method.try_next
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.step() {
+            StepOutcome::Continue | StepOutcome::Trapped(_) => Some(()),
+            StepOutcome::Terminated | StepOutcome::TimedOut => None,
+        }
+    }
+}
+
+impl distributions::Distribution<Direction> for distributions::Standard {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Direction {
+        Direction::from_u8(rng.gen_range(0, 4)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playfield::Playfield;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    fn interpreter(program: &str) -> Interpreter<&'static [u8], Vec<u8>> {
+        Interpreter::new(Playfield::new(program), InputOutput::new(&[], Vec::new()))
+    }
+
+    fn test_program(field: &str, input: &str, output: &str, execution: Vec<(Mode, Stack)>) {
+        let reader = input.bytes().collect::<Vec<_>>();
+        let writer = Vec::new();
+
+        let playfield = Playfield::new(field);
+        let io = InputOutput::new(&reader[..], writer);
+        let mut interpreter = Interpreter::new(playfield, io);
+
+        for (mode, stack) in execution {
+            assert_eq!(mode, interpreter.mode);
+            assert_eq!(stack, interpreter.stack);
+
+            interpreter.next();
+        }
+
+        assert_eq!(output.bytes().collect::<Vec<_>>(), interpreter.io.writer);
+    }
+
+    #[test]
+    fn interpret_digits() {
+        test_program(
+            "0123456789",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![0]),
+                (Mode::Execute, vec![0, 1]),
+                (Mode::Execute, vec![0, 1, 2]),
+                (Mode::Execute, vec![0, 1, 2, 3]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4, 5]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]),
+                (Mode::Execute, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_arithmetic() {
+        test_program(
+            "73+",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![10]),
+            ],
+        );
+
+        test_program(
+            "73-",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![4]),
+            ],
+        );
+
+        test_program(
+            "73*",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![21]),
+            ],
+        );
+
+        test_program(
+            "73/",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![2]),
+            ],
+        );
+
+        test_program(
+            "73%",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![1]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_logic() {
+        test_program(
+            "0!",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![0]),
+                (Mode::Execute, vec![1]),
+            ],
+        );
+
+        test_program(
+            "5!",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+                (Mode::Execute, vec![0]),
+            ],
+        );
+
+        test_program(
+            "73`",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 3]),
+                (Mode::Execute, vec![1]),
+            ],
+        );
+
+        test_program(
+            "45`",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![4]),
+                (Mode::Execute, vec![4, 5]),
+                (Mode::Execute, vec![0]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_direction() {
+        test_program(
+            "v\n3\n>4",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![3, 4]),
+            ],
+        );
+
+        test_program(
+            "<@^\n  @\n  5",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+            ],
+        );
+
+        test_program(
+            "?5@5\n5\n@\n5",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_controlflow() {
+        test_program(
+            "0_5",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![0]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+            ],
+        );
+
+        test_program(
             "3_5",
             "",
             "",
             vec![
                 (Mode::Execute, vec![]),
-                (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![3]),
+            ],
+        );
+
+        test_program(
+            "0|\n 5",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![0]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+            ],
+        );
+
+        test_program(
+            "3|\n 5\n 4",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![4]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_string() {
+        test_program(
+            "\"abc\"",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Parse, vec![]),
+                (Mode::Parse, vec![0x61]),
+                (Mode::Parse, vec![0x61, 0x62]),
+                (Mode::Parse, vec![0x61, 0x62, 0x63]),
+                (Mode::Execute, vec![0x61, 0x62, 0x63]),
+            ],
+        );
+
+        test_program(
+            "1\"23",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+                (Mode::Parse, vec![1]),
+                (Mode::Parse, vec![1, 0x32]),
+                (Mode::Parse, vec![1, 0x32, 0x33]),
+                (Mode::Parse, vec![1, 0x32, 0x33, 0x31]),
+                (Mode::Execute, vec![1, 0x32, 0x33, 0x31]),
+            ],
+        );
+
+        test_program(
+            "v\n\"\na\nb\n\"",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Parse, vec![]),
+                (Mode::Parse, vec![0x61]),
+                (Mode::Parse, vec![0x61, 0x62]),
+                (Mode::Execute, vec![0x61, 0x62]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_parse_len() {
+        let mut interpreter = interpreter("\"abc\"@");
+
+        assert_eq!(None, interpreter.parse_len());
+
+        interpreter.next();
+        assert_eq!(Some(0), interpreter.parse_len());
+
+        interpreter.next();
+        assert_eq!(Some(1), interpreter.parse_len());
+
+        interpreter.next();
+        assert_eq!(Some(2), interpreter.parse_len());
+
+        interpreter.next();
+        assert_eq!(Some(3), interpreter.parse_len());
+
+        interpreter.next();
+        assert_eq!(None, interpreter.parse_len());
+    }
+
+    #[test]
+    fn interpret_pending_string() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::with_stack(Playfield::new("\"abc\"@"), io, vec![7]);
+
+        assert_eq!(None, interpreter.pending_string());
+
+        interpreter.next();
+        assert_eq!(Some(String::new()), interpreter.pending_string());
+
+        interpreter.next();
+        interpreter.next();
+        interpreter.next();
+        assert_eq!(Some("abc".to_string()), interpreter.pending_string());
+
+        interpreter.next();
+        assert_eq!(None, interpreter.pending_string());
+        assert_eq!(&vec![7, 0x61, 0x62, 0x63], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_last_effect() {
+        let reader: &[u8] = b"1";
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("12+.~00p@"), io);
+
+        let effects = vec![
+            StepEffect::None,
+            StepEffect::None,
+            StepEffect::None,
+            StepEffect::OutputWritten,
+            StepEffect::InputRead,
+            StepEffect::None,
+            StepEffect::None,
+            StepEffect::FieldModified,
+        ];
+
+        for effect in effects {
+            interpreter.next();
+            assert_eq!(effect, interpreter.last_effect());
+        }
+    }
+
+    #[test]
+    fn interpret_run_to_completion() {
+        let mut interpreter = interpreter("123$$$@");
+
+        assert_eq!(StepOutcome::Terminated, interpreter.run_to_completion());
+        assert_eq!(7, interpreter.steps());
+        assert_eq!(3, interpreter.max_stack_depth());
+
+        assert_eq!(StepOutcome::Terminated, interpreter.step());
+        assert_eq!(7, interpreter.steps());
+    }
+
+    #[test]
+    fn interpret_trap() {
+        let mut interpreter = interpreter("12.\".\"@");
+
+        interpreter.add_trap(b'.');
+
+        assert_eq!(
+            StepOutcome::Trapped((2, 0)),
+            interpreter.run_to_completion()
+        );
+        assert_eq!((2, 0), interpreter.nav().pos());
+        assert_eq!(&vec![1, 2], interpreter.stack());
+        assert!(interpreter.io().writer().is_empty());
+
+        assert_eq!(StepOutcome::Terminated, interpreter.run_to_completion());
+        assert_eq!(b"2 ", &interpreter.io().writer()[..]);
+    }
+
+    #[test]
+    fn interpret_breakpoint() {
+        let mut interpreter = interpreter("1v\n@<");
+
+        interpreter.add_breakpoint((1, 1));
+
+        assert_eq!(
+            StepOutcome::Trapped((1, 1)),
+            interpreter.run_to_completion()
+        );
+        assert_eq!(2, interpreter.steps());
+
+        assert_eq!(StepOutcome::Terminated, interpreter.run_to_completion());
+    }
+
+    #[test]
+    fn interpret_list_breakpoints() {
+        let mut interpreter = interpreter("12.@");
+
+        interpreter.add_breakpoint_with_count((2, 0), 2);
+        interpreter.add_breakpoint((1, 0));
+        interpreter.add_trap(b'.');
+        interpreter.step();
+        interpreter.step();
+        interpreter.step();
+
+        assert_eq!(StepOutcome::Trapped((2, 0)), interpreter.step());
+        assert_eq!(
+            vec![
+                BreakpointInfo::Position {
+                    pos: (1, 0),
+                    count: None,
+                    hits: 1,
+                },
+                BreakpointInfo::Position {
+                    pos: (2, 0),
+                    count: Some(2),
+                    hits: 0,
+                },
+                BreakpointInfo::Instruction(b'.'),
+            ],
+            interpreter.breakpoints()
+        );
+    }
+
+    #[test]
+    fn interpret_breakpoint_with_count() {
+        let mut interpreter = interpreter(">v\n^<");
+
+        interpreter.add_breakpoint_with_count((1, 0), 3);
+
+        assert_eq!(
+            StepOutcome::Trapped((1, 0)),
+            interpreter.run_to_completion()
+        );
+        assert_eq!(9, interpreter.steps());
+
+        let mut interpreter = self::interpreter("1x@");
+
+        interpreter.add_breakpoint_with_count((1, 0), 2);
+        interpreter.step();
+
+        for _ in 0..3 {
+            assert!(interpreter.try_step().is_err());
+        }
+
+        assert_eq!(
+            vec![BreakpointInfo::Position {
+                pos: (1, 0),
+                count: Some(2),
+                hits: 0,
+            }],
+            interpreter.breakpoints()
+        );
+    }
+
+    #[test]
+    fn interpret_rewind() {
+        let mut interpreter = interpreter("12345@");
+
+        interpreter.set_history_depth(10);
+
+        interpreter.step();
+        interpreter.step();
+
+        let state = interpreter.snapshot();
+
+        interpreter.step();
+        interpreter.step();
+        interpreter.step();
+
+        assert_eq!(3, interpreter.rewind(3));
+        assert_eq!(state, interpreter.snapshot());
+        assert_eq!(&vec![1, 2], interpreter.stack());
+
+        assert_eq!(2, interpreter.rewind(5));
+        assert_eq!(0, interpreter.rewind(1));
+        assert_eq!((0, 0), interpreter.nav().pos());
+
+        interpreter.set_history_depth(2);
+        interpreter.run_to_completion();
+
+        assert_eq!(2, interpreter.history_len());
+    }
+
+    #[test]
+    fn interpret_string_literal_terminate() {
+        test_program(
+            "\"@\"@",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Parse, vec![]),
+                (Mode::Parse, vec![0x40]),
+                (Mode::Execute, vec![0x40]),
+                (Mode::Terminate, vec![0x40]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_string_unterminated() {
+        test_program(
+            "\"12",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Parse, vec![]),
+                (Mode::Parse, vec![0x31]),
+                (Mode::Parse, vec![0x31, 0x32]),
+                (Mode::Execute, vec![0x31, 0x32]),
+                (Mode::Execute, vec![0x31, 0x32, 1]),
+                (Mode::Execute, vec![0x31, 0x32, 1, 2]),
+                (Mode::Parse, vec![0x31, 0x32, 1, 2]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_checkpoint_rollback() {
+        let mut interpreter = interpreter("12345@");
+
+        interpreter.step();
+        interpreter.checkpoint("one");
+
+        interpreter.step();
+        interpreter.step();
+        interpreter.checkpoint("three");
+
+        interpreter.run_to_completion();
+
+        assert!(!interpreter.rollback("two"));
+
+        assert!(interpreter.rollback("one"));
+        assert_eq!(&vec![1], interpreter.stack());
+        assert_eq!((1, 0), interpreter.nav().pos());
+        assert_eq!(1, interpreter.steps());
+
+        assert!(interpreter.rollback("three"));
+        assert_eq!(&vec![1, 2, 3], interpreter.stack());
+        assert_eq!((3, 0), interpreter.nav().pos());
+        assert_eq!(Mode::Execute, interpreter.mode());
+
+        assert!(interpreter.rollback("one"));
+        assert_eq!(&vec![1], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_initial_stack() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::with_stack(Playfield::new("*.@"), io, vec![2, 3]);
+
+        assert_eq!(&vec![2, 3], interpreter.stack());
+
+        interpreter.run_to_completion();
+
+        assert_eq!(b"6 ", &interpreter.io().writer()[..]);
+    }
+
+    struct WarningCollector(Arc<Mutex<Vec<Warning>>>);
+
+    impl Observer for WarningCollector {
+        fn on_warning(&mut self, warning: Warning) {
+            self.0.lock().unwrap().push(warning);
+        }
+    }
+
+    #[test]
+    fn interpret_truncated_put() {
+        for &(value, stored, strict) in &[(300, 44, false), (-1, 255, true), (65, 65, true)] {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let field = Playfield::new("p50g@ ");
+            let mut interpreter = Interpreter::with_stack(field, io, vec![value, 5, 0]);
+
+            let warnings = Arc::new(Mutex::new(Vec::new()));
+            interpreter.set_observer(Box::new(WarningCollector(warnings.clone())));
+            interpreter.set_strict(strict);
+
+            interpreter.run_to_completion();
+
+            assert_eq!(&vec![stored], interpreter.stack());
+
+            let expected = if strict && stored != value {
+                vec![Warning::TruncatedPut { pos: (5, 0), value }]
+            } else {
+                vec![]
+            };
+
+            assert_eq!(expected, *warnings.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn interpret_wide_cells() {
+        for &(cell_type, stored) in &[(CellType::Byte, 232), (CellType::Wide, 1000)] {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let mut field = Playfield::new("p50g@ ");
+            field.set_cell_type(cell_type);
+            let mut interpreter = Interpreter::with_stack(field, io, vec![1000, 5, 0]);
+
+            interpreter.run_to_completion();
+
+            assert_eq!(&vec![stored], interpreter.stack());
+            assert_eq!(232, interpreter.field()[(5, 0)]);
+        }
+    }
+
+    #[test]
+    fn interpret_feed_and_step() {
+        let io = InputOutput::new(VecDeque::new(), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("1~@"), io);
+
+        assert!(!interpreter.awaits_input());
+        interpreter.feed_and_step(Some(b"x"));
+        assert!(interpreter.io().reader().is_empty());
+
+        assert!(interpreter.awaits_input());
+        interpreter.feed_and_step(Some(b"A"));
+
+        assert_eq!(StepEffect::InputRead, interpreter.last_effect());
+        assert_eq!(&vec![1, 65], interpreter.stack());
+        assert!(interpreter.io().reader().is_empty());
+        assert_eq!(StepOutcome::Terminated, interpreter.feed_and_step(None));
+    }
+
+    struct DefaultedCollector(Arc<Mutex<Vec<(usize, usize)>>>);
+
+    impl Observer for DefaultedCollector {
+        fn on_input_defaulted(&mut self, pos: (usize, usize)) {
+            self.0.lock().unwrap().push(pos);
+        }
+    }
+
+    struct ModeCollector(Arc<Mutex<Vec<(Mode, Mode)>>>);
+
+    impl Observer for ModeCollector {
+        fn on_mode_change(&mut self, from: Mode, to: Mode) {
+            self.0.lock().unwrap().push((from, to));
+        }
+    }
+
+    #[test]
+    fn interpret_mode_change() {
+        let mut interpreter = interpreter("\"ab\"1@");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        interpreter.set_observer(Box::new(ModeCollector(changes.clone())));
+
+        interpreter.run_to_completion();
+        interpreter.step();
+
+        assert_eq!(
+            vec![
+                (Mode::Execute, Mode::Parse),
+                (Mode::Parse, Mode::Execute),
+                (Mode::Execute, Mode::Terminate),
+            ],
+            *changes.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn interpret_input_defaulted() {
+        for &(input, effect, stack, defaulted) in &[
+            ("", StepEffect::InputDefaulted, 0, true),
+            ("abc", StepEffect::InputDefaulted, 0, true),
+            ("5", StepEffect::InputRead, 5, false),
+        ] {
+            let io = InputOutput::new(input.as_bytes(), Vec::new());
+            let mut interpreter = Interpreter::new(Playfield::new(" &@"), io);
+
+            let positions = Arc::new(Mutex::new(Vec::new()));
+            interpreter.set_observer(Box::new(DefaultedCollector(positions.clone())));
+
+            interpreter.step();
+            interpreter.step();
+
+            assert_eq!(effect, interpreter.last_effect());
+            assert_eq!(&vec![stack], interpreter.stack());
+
+            let expected = if defaulted { vec![(1, 0)] } else { vec![] };
+
+            assert_eq!(expected, *positions.lock().unwrap());
+        }
+
+        let mut interpreter = interpreter("~@");
+
+        interpreter.step();
+
+        assert_eq!(StepEffect::InputDefaulted, interpreter.last_effect());
+        assert_eq!(&vec![-1], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_stack_manipulation() {
+        test_program(
+            "1:",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+                (Mode::Execute, vec![1, 1]),
+            ],
+        );
+
+        test_program(
+            "12\\",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+                (Mode::Execute, vec![1, 2]),
+                (Mode::Execute, vec![2, 1]),
+            ],
+        );
+
+        test_program(
+            "1$",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+                (Mode::Execute, vec![]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_output() {
+        test_program(
+            "1.",
+            "",
+            "1 ",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+                (Mode::Execute, vec![]),
+            ],
+        );
+
+        test_program(
+            "\"a\",25*,",
+            "",
+            "a\n",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Parse, vec![]),
+                (Mode::Parse, vec![0x61]),
+                (Mode::Execute, vec![0x61]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![2]),
+                (Mode::Execute, vec![2, 5]),
+                (Mode::Execute, vec![10]),
+                (Mode::Execute, vec![]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_bridge() {
+        test_program(
+            "#01",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_field_manipulation() {
+        test_program(
+            "30g5",
+            "",
+            "",
+            vec![
                 (Mode::Execute, vec![]),
                 (Mode::Execute, vec![3]),
+                (Mode::Execute, vec![3, 0]),
+                (Mode::Execute, vec![0x35]),
+                (Mode::Execute, vec![0x35, 5]),
+            ],
+        );
+
+        test_program(
+            "77*40p1",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![7]),
+                (Mode::Execute, vec![7, 7]),
+                (Mode::Execute, vec![49]),
+                (Mode::Execute, vec![49, 4]),
+                (Mode::Execute, vec![49, 4, 0]),
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![1]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_user_input() {
+        test_program(
+            "&",
+            "4\n",
+            "",
+            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![4])],
+        );
+
+        test_program(
+            "&",
+            "abc\n",
+            "",
+            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![0])],
+        );
+
+        test_program(
+            "&",
+            "",
+            "",
+            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![0])],
+        );
+
+        test_program(
+            "~~~",
+            "ab",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![0x61]),
+                (Mode::Execute, vec![0x61, 0x62]),
+                (Mode::Execute, vec![0x61, 0x62, -1]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_user_input_tokens() {
+        test_program(
+            "&&&&",
+            "10 20 30\n",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![10]),
+                (Mode::Execute, vec![10, 20]),
+                (Mode::Execute, vec![10, 20, 30]),
+                (Mode::Execute, vec![10, 20, 30, 0]),
+            ],
+        );
+
+        test_program(
+            "&~&",
+            "-5\nx 7",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![-5]),
+                (Mode::Execute, vec![-5, 0x78]),
+                (Mode::Execute, vec![-5, 0x78, 7]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_io_mut() {
+        let io = InputOutput::new(io::Cursor::new(Vec::new()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("~~@"), io);
+
+        interpreter.step();
+        assert_eq!(&vec![-1], interpreter.stack());
+
+        interpreter.io_mut().reader_mut().get_mut().extend(b"a");
+        interpreter.step();
+        assert_eq!(&vec![-1, 0x61], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_int_separator() {
+        let output = |separator| {
+            let reader: &[u8] = &[];
+            let mut io = InputOutput::new(reader, Vec::new());
+            io.set_int_separator(separator);
+
+            let mut interpreter = Interpreter::new(Playfield::new("5.@"), io);
+            interpreter.run_to_completion();
+
+            String::from_utf8(interpreter.io().writer().clone()).unwrap()
+        };
+
+        assert_eq!("5 ", output(IntSeparator::Space));
+        assert_eq!("5\n", output(IntSeparator::Newline));
+        assert_eq!("5", output(IntSeparator::None));
+    }
+
+    #[test]
+    fn interpret_output_encoding() {
+        let output = |encoding, stack| {
+            let reader: &[u8] = &[];
+            let mut io = InputOutput::new(reader, Vec::new());
+            io.set_output_encoding(encoding);
+
+            let mut interpreter = Interpreter::with_stack(Playfield::new(",,@"), io, stack);
+            interpreter.run_to_completion();
+
+            String::from_utf8(interpreter.io().writer().clone()).unwrap()
+        };
+
+        assert_eq!(
+            "\u{c3}\u{a9}",
+            output(OutputEncoding::Raw, vec![0xa9, 0xc3])
+        );
+        assert_eq!("é", output(OutputEncoding::Utf8Checked, vec![0xa9, 0xc3]));
+        assert_eq!(
+            "\u{fffd}A",
+            output(OutputEncoding::Utf8Checked, vec![0x41, 0xff])
+        );
+        assert_eq!(
+            "a\u{fffd}",
+            output(OutputEncoding::Utf8Checked, vec![0xc3, 0x61])
+        );
+    }
+
+    #[test]
+    fn interpret_termination() {
+        test_program(
+            "@",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Terminate, vec![]),
+                (Mode::Terminate, vec![]),
             ],
         );
 
-        test_program(
-            "0|\n 5",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![0]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-            ],
-        );
+        test_program(
+            "5@",
+            "",
+            "",
+            vec![
+                (Mode::Execute, vec![]),
+                (Mode::Execute, vec![5]),
+                (Mode::Terminate, vec![5]),
+                (Mode::Terminate, vec![5]),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpret_current_instruction_name() {
+        let mut interpreter = interpreter("23*.@ x");
+
+        assert_eq!("push 2", interpreter.current_instruction_name());
+
+        interpreter.step();
+        interpreter.step();
+
+        assert_eq!("multiply", interpreter.current_instruction_name());
+
+        interpreter.run_to_completion();
+
+        assert_eq!("end", interpreter.current_instruction_name());
+
+        let mut interpreter = self::interpreter(" x");
+
+        assert_eq!("space", interpreter.current_instruction_name());
+
+        interpreter.step();
+
+        assert_eq!("illegal", interpreter.current_instruction_name());
+    }
+
+    #[test]
+    fn interpret_lahey_space() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let field = Playfield::new("^\n@\n1\n \n ");
+        let mut interpreter = Interpreter::new(field.clone(), io);
+
+        interpreter.set_wrap_mode(WrapMode::LaheySpace);
+        interpreter.run_to_completion();
+
+        assert_eq!(3, interpreter.steps());
+        assert_eq!(&vec![1], interpreter.stack());
+
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(field, io);
+
+        interpreter.run_to_completion();
+
+        assert_eq!(5, interpreter.steps());
+    }
+
+    #[test]
+    fn interpret_coverage() {
+        let mut interpreter = interpreter("0 #v_@\n   >1@");
+
+        interpreter.run_to_completion();
+        assert!(interpreter.executed_cells().is_empty());
+
+        let mut interpreter = self::interpreter("0 #v_@\n   >1@");
+
+        interpreter.set_coverage_tracking(true);
+        interpreter.run_to_completion();
+
+        let cells = interpreter.executed_cells();
+
+        assert!(cells.contains(&(4, 0)));
+        assert!(cells.contains(&(5, 0)));
+        assert!(!cells.contains(&(3, 0)));
+        assert!(!cells.contains(&(4, 1)));
+        assert_eq!(5, cells.len());
+        assert_eq!(50.0, interpreter.coverage());
+    }
+
+    #[test]
+    fn interpret_execution_count() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let field = Playfield::new("v  >1-:v\n>4 ^    \n   ^   _@");
+        let mut interpreter = Interpreter::new(field, io);
+
+        interpreter.set_coverage_tracking(true);
+        interpreter.run_to_completion();
+
+        assert_eq!(1, interpreter.execution_count(0, 0));
+        assert_eq!(1, interpreter.execution_count(1, 1));
+        assert_eq!(4, interpreter.execution_count(4, 0));
+        assert_eq!(4, interpreter.execution_count(7, 2));
+        assert_eq!(1, interpreter.execution_count(8, 2));
+        assert_eq!(0, interpreter.execution_count(8, 0));
+        assert_eq!(0, interpreter.execution_count(9, 0));
+        assert_eq!(0, interpreter.execution_count(0, 3));
+    }
+
+    #[test]
+    fn interpret_cardinal_befunge93() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let field = Playfield::new(include_str!("../examples/sieve_of_eratosthenes.bf"));
+        let mut interpreter = Interpreter::new(field, io);
+
+        while let StepOutcome::Continue = interpreter.step() {
+            assert!(interpreter.nav().dir().is_some());
+        }
+    }
+
+    #[test]
+    fn interpret_funge98() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let field = Playfield::new("11x  \n     \n    @");
+        let mut interpreter = Interpreter::new(field, io);
+
+        interpreter.set_dialect(Dialect::Funge98);
+        interpreter.run_to_completion();
+
+        assert_eq!((4, 2), interpreter.nav().pos());
+        assert_eq!((1, 1), interpreter.nav().delta());
+        assert_eq!(None, interpreter.nav().dir());
+
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let field = Playfield::new("fna5w\n    @");
+        let mut interpreter = Interpreter::new(field, io);
+
+        interpreter.set_dialect(Dialect::Funge98);
+        interpreter.run_to_completion();
+
+        assert_eq!((4, 1), interpreter.nav().pos());
+        assert!(interpreter.stack().is_empty());
+    }
+
+    #[test]
+    fn interpret_extreme_delta() {
+        let mut interpreter = interpreter("2:*:*:*:*:*:2/*1-0x@");
+
+        interpreter.set_dialect(Dialect::Funge98);
+        interpreter.run_with_limit(100).unwrap();
+        assert_eq!((i64::MAX, 0), interpreter.nav().delta());
+
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::with_stack(Playfield::new("xr@"), io, vec![i64::MIN, 0]);
+
+        interpreter.set_dialect(Dialect::Funge98);
+        assert_eq!(
+            StepOutcome::Terminated,
+            interpreter.run_with_limit(3).unwrap()
+        );
+        assert_eq!((i64::MIN, 0), interpreter.nav().delta());
+        assert_eq!((2, 0), interpreter.nav().pos());
+    }
+
+    #[test]
+    fn interpret_bridge_delta() {
+        let mut interpreter = interpreter("#12@");
+
+        interpreter.step();
+        assert_eq!((2, 0), interpreter.nav().pos());
+
+        interpreter.run_to_completion();
+        assert_eq!(&vec![2], interpreter.stack());
+
+        let mut interpreter = self::interpreter("20x # 1 2 @");
+
+        interpreter.set_dialect(Dialect::Funge98);
+        interpreter.run_with_limit(3).unwrap();
+        assert_eq!((4, 0), interpreter.nav().pos());
+
+        interpreter.step();
+        assert_eq!((8, 0), interpreter.nav().pos());
+
+        interpreter.run_to_completion();
+        assert_eq!(&vec![2], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_deadline() {
+        use std::time::Duration;
+
+        let mut interpreter = interpreter(">");
+
+        interpreter.set_deadline_check_interval(16);
+        let deadline = Instant::now() + Duration::from_millis(10);
+        assert_eq!(
+            StepOutcome::TimedOut,
+            interpreter.run_to_completion_until(deadline)
+        );
+        assert_eq!(0, interpreter.steps() % 16);
+
+        let mut interpreter = self::interpreter("23*.@");
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            StepOutcome::Terminated,
+            interpreter.run_to_completion_until(deadline)
+        );
+        assert_eq!(b"6 ", &interpreter.io().writer()[..]);
+    }
+
+    #[test]
+    fn interpret_no_op() {
+        let mut interpreter = interpreter("1;2;;@");
+
+        interpreter.add_no_op(b';');
+
+        interpreter.step();
+        assert_eq!("no-op", interpreter.current_instruction_name());
+
+        assert_eq!(StepOutcome::Terminated, interpreter.run_to_completion());
+        assert_eq!(&vec![1, 2], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_no_op_removed() {
+        let mut interpreter = interpreter(";@");
+
+        interpreter.add_no_op(b';');
+        interpreter.remove_no_op(b';');
+
+        assert_eq!(
+            Err(StepError::IllegalInstruction {
+                pos: (0, 0),
+                instruction: b';',
+            }),
+            interpreter.run_with_limit(10)
+        );
+    }
+
+    #[test]
+    fn interpret_padding_value() {
+        for &(padding, value) in &[(PaddingValue::Space, 32), (PaddingValue::Zero, 0)] {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let mut field = Playfield::new("40g@\n12345");
+            field.set_padding_value(padding);
+            let mut interpreter = Interpreter::new(field, io);
+
+            interpreter.run_to_completion();
+
+            assert_eq!(&vec![value], interpreter.stack());
+        }
+    }
+
+    #[test]
+    fn interpret_get_outside() {
+        for &padding in &[PaddingValue::Space, PaddingValue::Zero] {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let mut field = Playfield::new("01-01-g 99*:*99*g 9099*-g 00g@");
+            field.set_padding_value(padding);
+            let mut interpreter = Interpreter::new(field, io);
+
+            interpreter.run_to_completion();
+
+            assert_eq!(&vec![0, 0, 0, 48], interpreter.stack());
+        }
+    }
+
+    #[test]
+    fn interpret_negative_coordinates() {
+        for &auto_grow in &[false, true] {
+            let program = "101-01-p901-p01-9p01-01-g@";
+            let mut interpreter = interpreter(program);
+
+            interpreter.set_auto_grow(auto_grow);
+
+            assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(100));
+            assert_eq!(&vec![0], interpreter.stack());
+            assert_eq!(program, interpreter.field().to_string().trim_end());
+            assert_eq!((26, 1), interpreter.field().dimensions());
+        }
+    }
+
+    #[test]
+    fn interpret_reset_io() {
+        let io = InputOutput::new(io::Cursor::new(b"3 4".to_vec()), Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("&&*.~.@"), io);
+        let start = interpreter.snapshot();
+
+        interpreter.run_to_completion();
+        let output = interpreter.io().writer().clone();
+
+        assert_eq!(b"12 -1 ".to_vec(), output);
+
+        interpreter.restore(start);
+        interpreter.reset_io();
+        assert!(interpreter.io().writer().is_empty());
+
+        interpreter.run_to_completion();
+
+        assert_eq!(&output, interpreter.io().writer());
+    }
+
+    #[test]
+    fn interpret_protect_region() {
+        let mut interpreter = interpreter("111p 022p @\n\n\n");
+
+        interpreter.protect_region((0, 3), (4, 1));
+        interpreter.protect_region((2, 1), (2, 2));
+
+        assert_eq!(Ok(StepOutcome::Continue), interpreter.run_with_limit(5));
+        assert_eq!(1, interpreter.field()[(1, 1)]);
+
+        assert_eq!(
+            Err(StepError::ProtectedWrite {
+                pos: (8, 0),
+                target: (2, 2)
+            }),
+            interpreter.run_with_limit(5)
+        );
+        assert_eq!((8, 0), interpreter.nav().pos());
+        assert_eq!(b' ', interpreter.field()[(2, 2)]);
+        assert_eq!(&vec![0, 2, 2], interpreter.stack());
+
+        let mut interpreter = self::interpreter("p@");
+
+        interpreter.protect_region((0, 0), (1, 1));
+        interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+        assert_eq!(
+            Err(StepError::StackUnderflow {
+                pos: (0, 0),
+                instruction: instr::PUT,
+            }),
+            interpreter.run_with_limit(5)
+        );
+    }
+
+    #[test]
+    fn interpret_protect_region_auto_grow() {
+        let mut interpreter = interpreter("962p@");
+
+        interpreter.set_auto_grow(true);
+        interpreter.protect_region((6, 2), (1, 1));
+
+        assert_eq!(
+            Err(StepError::ProtectedWrite {
+                pos: (3, 0),
+                target: (6, 2)
+            }),
+            interpreter.run_with_limit(10)
+        );
+        assert_eq!((5, 1), interpreter.field().dimensions());
+    }
+
+    #[test]
+    fn interpret_div_by_zero() {
+        let run = |program: &str, policy: DivByZeroPolicy| {
+            let mut interpreter = interpreter(program);
+
+            interpreter.set_div_by_zero_policy(policy);
+
+            let result = interpreter.run_with_limit(100);
+
+            (result, interpreter.stack().clone())
+        };
+
+        for program in &["70/7@", "70%7@"] {
+            assert_eq!(
+                (Ok(StepOutcome::Terminated), vec![0, 7]),
+                run(program, DivByZeroPolicy::Zero)
+            );
+            assert_eq!(
+                (Ok(StepOutcome::Terminated), vec![0, 7]),
+                run(
+                    &format!("#@70{}3@", &program[2..3]),
+                    DivByZeroPolicy::Reflect
+                )
+            );
+            assert_eq!(
+                (
+                    Err(StepError::DivideByZero {
+                        pos: (2, 0),
+                        instruction: program.as_bytes()[2],
+                    }),
+                    vec![7, 0]
+                ),
+                run(program, DivByZeroPolicy::Fail)
+            );
+        }
+
+        assert_eq!(
+            (Ok(StepOutcome::Terminated), vec![2]),
+            run("73/@", DivByZeroPolicy::Fail)
+        );
+
+        for program in &["/@", "0%@"] {
+            let mut interpreter = self::interpreter(program);
+
+            interpreter.set_div_by_zero_policy(DivByZeroPolicy::Fail);
+
+            assert_eq!(
+                Err(StepError::DivideByZero {
+                    pos: (program.len() - 2, 0),
+                    instruction: program.as_bytes()[program.len() - 2],
+                }),
+                interpreter.run_with_limit(10)
+            );
+
+            let mut interpreter = self::interpreter(program);
+
+            interpreter.set_div_by_zero_policy(DivByZeroPolicy::Fail);
+            interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+            assert_eq!(
+                Err(StepError::StackUnderflow {
+                    pos: (program.len() - 2, 0),
+                    instruction: program.as_bytes()[program.len() - 2],
+                }),
+                interpreter.run_with_limit(10)
+            );
+        }
+    }
+
+    #[test]
+    fn interpret_wrapping_arithmetic() {
+        let mut interpreter = Interpreter::with_stack(
+            Playfield::new("*1-@"),
+            NullInputOutput::default(),
+            vec![i64::MAX, 2],
+        );
+
+        interpreter.run_to_completion();
+        assert_eq!(&vec![-3], interpreter.stack());
+
+        let mut interpreter = Interpreter::with_stack(
+            Playfield::new("/@"),
+            NullInputOutput::default(),
+            vec![i64::MIN, -1],
+        );
+
+        interpreter.run_to_completion();
+        assert_eq!(&vec![i64::MIN], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_can_reach_exit() {
+        assert!(interpreter("v@<\n>>^").can_reach_exit());
+        assert!(interpreter("0v\n<_@").can_reach_exit());
+        assert!(interpreter("v<\np^").can_reach_exit());
+
+        assert!(!interpreter("v<\n>^").can_reach_exit());
+        assert!(!interpreter("#@v\n  <").can_reach_exit());
+        assert!(!interpreter("v\n\"\n@\n\"\n>^").can_reach_exit());
+
+        let mut looping = interpreter("0_v@\n  >v\n  ^<");
+
+        assert!(looping.can_reach_exit());
+
+        for _ in 0..3 {
+            looping.step();
+        }
+
+        assert_eq!((2, 1), looping.nav().pos());
+        assert!(!looping.can_reach_exit());
+    }
+
+    #[test]
+    fn interpret_can_reach_stop() {
+        let mut illegal = interpreter("v<\nx^");
+
+        assert!(illegal.can_reach_exit());
+        illegal.set_illegal_policy(IllegalPolicy::Skip);
+        assert!(!illegal.can_reach_exit());
+
+        let mut trapped = interpreter("v<\n1^");
+
+        trapped.add_trap(b'1');
+        assert!(trapped.can_reach_exit());
+        assert_eq!(StepOutcome::Trapped((0, 1)), trapped.run_to_completion());
+        assert!(trapped.can_reach_exit());
+
+        let mut dividing = interpreter("v<\n/^");
+
+        assert!(!dividing.can_reach_exit());
+        dividing.set_div_by_zero_policy(DivByZeroPolicy::Fail);
+        assert!(dividing.can_reach_exit());
+
+        let mut field = Playfield::new("> ");
+
+        field.set_cell_type(CellType::Wide);
+        field.set_value((1, 0), 256 + i64::from(instr::TERMINATE));
+
+        let io = InputOutput::new(&[][..], Vec::new());
+        let mut wide = Interpreter::new(field, io);
+
+        wide.set_illegal_policy(IllegalPolicy::Skip);
+
+        assert!(!wide.can_reach_exit());
+        assert_eq!(Ok(StepOutcome::Continue), wide.run_with_limit(10));
+        assert_eq!(
+            Some(&(1, 0, instr::TERMINATE)),
+            wide.skipped_illegals().first()
+        );
+    }
+
+    #[test]
+    fn interpret_auto_grow() {
+        let run = |policy: GrowPolicy| {
+            let mut interpreter = interpreter("171p199*9p@");
+
+            interpreter.set_auto_grow(true);
+            interpreter.set_max_field_cells(100);
+            interpreter.set_grow_policy(policy);
+
+            let result = interpreter.run_with_limit(100);
+
+            (result, interpreter)
+        };
+
+        let (result, interpreter) = run(GrowPolicy::Ignore);
+
+        assert_eq!(Ok(StepOutcome::Terminated), result);
+        assert_eq!((11, 2), interpreter.field().dimensions());
+        assert_eq!(1, interpreter.field()[(7, 1)]);
+
+        let (result, interpreter) = run(GrowPolicy::Fail);
+
+        assert_eq!(
+            Err(StepError::FieldLimitExceeded {
+                pos: (9, 0),
+                target: (81, 9)
+            }),
+            result
+        );
+        assert_eq!((11, 2), interpreter.field().dimensions());
+        assert_eq!((9, 0), interpreter.nav().pos());
+
+        let mut interpreter = self::interpreter("99p@");
+
+        interpreter.set_auto_grow(true);
+        interpreter.set_max_field_cells(10);
+        interpreter.set_grow_policy(GrowPolicy::Fail);
+        interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+        assert_eq!(
+            Err(StepError::StackUnderflow {
+                pos: (2, 0),
+                instruction: instr::PUT,
+            }),
+            interpreter.run_with_limit(5)
+        );
+
+        let mut interpreter = self::interpreter("171p@");
+
+        interpreter.run_to_completion();
+        assert_eq!((5, 1), interpreter.field().dimensions());
+    }
+
+    #[test]
+    fn interpret_modulo_mode() {
+        // (b, a, b / a and b % a in Rust mode, b / a and b % a in Euclidean mode)
+        let cases = [
+            (-7, 3, (-2, -1), (-3, 2)),
+            (7, -3, (-2, 1), (-2, 1)),
+            (-7, -3, (2, -1), (3, 2)),
+            (7, 3, (2, 1), (2, 1)),
+        ];
+
+        let run = |program: &str, modulo: ModuloMode, b: i64, a: i64| {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let mut interpreter = Interpreter::with_stack(Playfield::new(program), io, vec![b, a]);
+
+            interpreter.set_modulo_mode(modulo);
+            interpreter.run_to_completion();
+            interpreter.stack()[0]
+        };
+
+        for &(b, a, rust, euclidean) in &cases {
+            for &(modulo, (div, rem)) in
+                &[(ModuloMode::Rust, rust), (ModuloMode::Euclidean, euclidean)]
+            {
+                assert_eq!(div, run("/@", modulo, b, a), "{} / {}", b, a);
+                assert_eq!(rem, run("%@", modulo, b, a), "{} % {}", b, a);
+                assert_eq!(b, a * div + rem);
+            }
+        }
+    }
+
+    #[test]
+    fn interpret_resize_field() {
+        let mut interpreter = interpreter("12v\n  3\n  @");
+
+        interpreter.set_coverage_tracking(true);
+        interpreter.set_history_depth(10);
+        interpreter.run_with_limit(3).unwrap();
+        assert_eq!((2, 1), interpreter.nav().pos());
+
+        interpreter.resize_field(2, 4);
+        interpreter.assert_consistent();
+
+        assert_eq!((2, 4), interpreter.nav().dimensions());
+        assert_eq!((1, 1), interpreter.nav().pos());
+        assert_eq!(1, interpreter.execution_count(1, 0));
+
+        interpreter.rewind(1);
+        interpreter.assert_consistent();
+
+        assert_eq!((3, 3), interpreter.nav().dimensions());
+        assert_eq!(0, interpreter.execution_count(2, 1));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "navigator dimensions")]
+    fn interpret_inconsistent_dimensions() {
+        let mut interpreter = interpreter("@");
+
+        interpreter.nav = PlayfieldNavigator::new((5, 5));
+        interpreter.assert_consistent();
+    }
+
+    #[test]
+    fn interpret_whitespace_input() {
+        for program in &["@\n", "@\n\n"] {
+            let field = Playfield::new(program);
+            let mut interpreter = Interpreter::new(field, NullInputOutput::default());
+
+            assert_eq!(StepOutcome::Terminated, interpreter.run_to_completion());
+            assert_eq!(1, interpreter.steps());
+        }
+
+        let field = Playfield::new("\n");
+        let mut interpreter = Interpreter::new(field, NullInputOutput::default());
+
+        assert_eq!(Ok(StepOutcome::Continue), interpreter.run_with_limit(10));
+        assert_eq!(10, interpreter.steps());
+    }
+
+    #[test]
+    fn interpret_output_as_ints() {
+        let mut interpreter = interpreter("34*.94*2-.@");
+
+        interpreter.run_to_completion();
+        assert_eq!(vec![12, 34], interpreter.output_as_ints());
+
+        let mut interpreter = self::interpreter("12.34.@");
+
+        interpreter.run_to_completion();
+        assert_eq!(vec![2, 4], interpreter.output_as_ints());
+
+        let mut interpreter = self::interpreter("05-.\" x\",,5.@");
+
+        interpreter.run_to_completion();
+        assert_eq!(vec![-5, 5], interpreter.output_as_ints());
+    }
+
+    #[test]
+    fn interpret_state_report() {
+        let mut interpreter = interpreter("88*7.v\n    @<");
+
+        for _ in 0..5 {
+            interpreter.step();
+        }
+
+        assert_eq!(
+            "Playfield:\n88*7.v\n     ^\n    @<\n\
+             Stack: [64 '@']\n\
+             Position: (5, 0)\n\
+             Direction: Right\n\
+             Mode: Execute\n\
+             Steps: 5\n\
+             Output: \"7 \"\n",
+            interpreter.state_report()
+        );
+    }
+
+    #[test]
+    fn interpret_outputs_equal() {
+        assert!(outputs_equal("23*.@", "6.@", "", 100));
+        assert!(outputs_equal("&.@", "&1-1+.@", "7", 100));
+        assert!(!outputs_equal("23*.@", "7.@", "", 100));
+        assert!(!outputs_equal("6.@", "6. >", "", 100));
+        assert!(!outputs_equal("6.@", "6.x", "", 100));
+    }
+
+    #[test]
+    fn format_stack_ascii() {
+        assert_eq!("[]", format_stack(&[]));
+        assert_eq!(
+            "[72 'H', -1, 300, 32 ' ']",
+            format_stack(&[72, -1, 300, 32])
+        );
+    }
+
+    #[test]
+    fn format_stack_vertical_lines() {
+        assert_eq!("", format_stack_vertical(&[], true));
+        assert_eq!(
+            "2: 300\n1: -1\n0: 72 'H'",
+            format_stack_vertical(&[72, -1, 300], true)
+        );
+        assert_eq!(
+            "0: 72 'H'\n1: -1\n2: 300",
+            format_stack_vertical(&[72, -1, 300], false)
+        );
+    }
+
+    #[test]
+    fn interpret_run_with_limit() {
+        let mut interpreter = Interpreter::new(Playfield::new("1>"), NullInputOutput::default());
+
+        assert_eq!(Ok(StepOutcome::Continue), interpreter.run_with_limit(10));
+        assert_eq!(10, interpreter.steps());
+
+        let mut interpreter = Interpreter::new(Playfield::new("1@"), NullInputOutput::default());
+
+        assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(10));
+        assert_eq!(2, interpreter.steps());
+    }
+
+    #[test]
+    fn interpret_instruction_constants() {
+        let program: Vec<u8> = vec![instr::PUSH_2, instr::PUSH_3, instr::ADD, instr::TERMINATE];
+        let mut interpreter = interpreter(str::from_utf8(&program).unwrap());
+
+        interpreter.run_to_completion();
+
+        assert_eq!(&vec![5], interpreter.stack());
+    }
+
+    #[test]
+    fn interpret_skip_illegal() {
+        let mut interpreter = interpreter("1x2v\n   ;\n   @");
+
+        interpreter.set_illegal_policy(IllegalPolicy::Skip);
+
+        assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(100));
+        assert_eq!(&vec![1, 2], interpreter.stack());
+        assert_eq!(
+            &[(1, 0, b'x'), (3, 1, b';')],
+            interpreter.skipped_illegals()
+        );
+    }
+
+    #[test]
+    fn interpret_empty_pop() {
+        let mut interpreter = interpreter("+.@");
+
+        assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(10));
+        assert_eq!(b"0 ", &interpreter.io().writer()[..]);
+
+        for program in &["+.@", "1+.@"] {
+            let mut interpreter = self::interpreter(program);
+
+            interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+            assert_eq!(
+                Err(StepError::StackUnderflow {
+                    pos: (program.len() - 3, 0),
+                    instruction: b'+',
+                }),
+                interpreter.run_with_limit(10)
+            );
+            assert_eq!((program.len() - 3, 0), interpreter.nav().pos());
+            assert_eq!(program.len() - 3, interpreter.stack().len());
+        }
+
+        let mut interpreter = self::interpreter("1\\@");
+
+        interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+        assert_eq!(
+            Err(StepError::StackUnderflow {
+                pos: (1, 0),
+                instruction: instr::SWAP,
+            }),
+            interpreter.run_with_limit(10)
+        );
+        assert_eq!(&vec![1], interpreter.stack());
+
+        let mut interpreter = self::interpreter("12+.@");
+
+        interpreter.set_empty_pop_policy(EmptyPopPolicy::Error);
+
+        assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(10));
+        assert_eq!(b"3 ", &interpreter.io().writer()[..]);
+    }
+
+    #[test]
+    fn interpret_step_record() {
+        let mut interpreter = interpreter("23*.@");
+
+        let records: Vec<StepRecord> = (0..4).map(|_| interpreter.step_record()).collect();
+        let stacks: Vec<&Stack> = records.iter().map(|r| &r.stack).collect();
+
+        assert_eq!(vec![&vec![2], &vec![2, 3], &vec![6], &vec![]], stacks);
+        assert_eq!(
+            StepRecord {
+                pos: (2, 0),
+                instruction: b'*',
+                mode_before: Mode::Execute,
+                mode_after: Mode::Execute,
+                stack: vec![6],
+                effect: StepEffect::None,
+                outcome: StepOutcome::Continue,
+            },
+            records[2]
+        );
+        assert_eq!(StepEffect::OutputWritten, records[3].effect);
+
+        let record = interpreter.step_record();
+
+        assert_eq!(Mode::Terminate, record.mode_after);
+        assert_eq!(StepOutcome::Terminated, record.outcome);
+    }
+
+    #[test]
+    fn interpret_force_random() {
+        let mut random = HashSet::new();
+
+        for _ in 0..200 {
+            let mut interpreter = interpreter("? ?");
+
+            interpreter.force_random_at((0, 0), Direction::Right);
+            assert_eq!(Some(Direction::Right), interpreter.predict_branch());
+
+            interpreter.step();
+            assert_eq!(Some(Direction::Right), interpreter.nav().dir());
+
+            interpreter.step();
+            assert_eq!(None, interpreter.predict_branch());
+
+            interpreter.step();
+            random.insert(interpreter.nav().delta());
+        }
+
+        assert!(random.len() > 1);
+
+        let mut interpreter = interpreter("?");
+        let start = interpreter.snapshot();
+
+        interpreter.force_random_once_at((0, 0), Direction::Up);
+        interpreter.step();
+
+        assert_eq!(Some(Direction::Up), interpreter.nav().dir());
+        assert_eq!(None, interpreter.predict_branch());
+
+        interpreter.restore(start);
+        assert_eq!(None, interpreter.predict_branch());
+
+        interpreter.force_random_at((0, 0), Direction::Left);
+        let start = interpreter.snapshot();
+
+        interpreter.step();
+        interpreter.restore(start);
+
+        assert_eq!(Some(Direction::Left), interpreter.predict_branch());
+    }
+
+    #[test]
+    fn interpret_alias() {
+        let mut interpreter = interpreter("23A.\"A\"B@");
+
+        interpreter.alias(b'A', b'+');
+        interpreter.alias(b'B', b'A');
+        interpreter.run_to_completion();
 
-        test_program(
-            "3|\n 5\n 4",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![3]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![4]),
-            ],
-        );
+        assert_eq!(b"5 ", &interpreter.io().writer()[..]);
+        assert_eq!(&vec![65], interpreter.stack());
+        assert_eq!(b'+', interpreter.resolve(b'B'));
+        assert_eq!(b'.', interpreter.resolve(b'.'));
+
+        let mut interpreter = self::interpreter("7AB.@");
+
+        interpreter.alias(b'A', b'B');
+        interpreter.alias(b'B', b'A');
+        interpreter.alias(b'B', b':');
+
+        assert_eq!(Ok(StepOutcome::Terminated), interpreter.run_with_limit(10));
+        assert_eq!(b"7 ", &interpreter.io().writer()[..]);
+        assert_eq!(&vec![7, 7], interpreter.stack());
+
+        interpreter.remove_alias(b'A');
+        assert!(!interpreter.aliases.contains_key(&b'A'));
+        assert_eq!(b'A', interpreter.resolve(b'A'));
     }
 
     #[test]
-    fn interpret_string() {
-        test_program(
-            "\"abc\"",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Parse, vec![]),
-                (Mode::Parse, vec![0x61]),
-                (Mode::Parse, vec![0x61, 0x62]),
-                (Mode::Parse, vec![0x61, 0x62, 0x63]),
-                (Mode::Execute, vec![0x61, 0x62, 0x63]),
-            ],
-        );
+    fn interpret_speculate() {
+        let reader: &[u8] = b"5";
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("&:.v\n@ 3_2 @"), io);
 
-        test_program(
-            "1\"23",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-                (Mode::Parse, vec![1]),
-                (Mode::Parse, vec![1, 0x32]),
-                (Mode::Parse, vec![1, 0x32, 0x33]),
-                (Mode::Parse, vec![1, 0x32, 0x33, 0x31]),
-                (Mode::Execute, vec![1, 0x32, 0x33, 0x31]),
-            ],
-        );
+        interpreter.set_modulo_mode(ModuloMode::Euclidean);
+        interpreter.run_with_limit(4).unwrap();
+        assert_eq!((3, 1), interpreter.nav().pos());
 
-        test_program(
-            "v\n\"\na\nb\n\"",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Parse, vec![]),
-                (Mode::Parse, vec![0x61]),
-                (Mode::Parse, vec![0x61, 0x62]),
-                (Mode::Execute, vec![0x61, 0x62]),
-            ],
-        );
+        let stacks: Vec<Stack> = [0, 1]
+            .iter()
+            .map(|&top| {
+                let mut speculative = interpreter.speculate();
+
+                speculative.stack.push(top);
+                speculative.run_to_completion();
+
+                assert_eq!(ModuloMode::Euclidean, speculative.modulo);
+                speculative.stack().clone()
+            })
+            .collect();
+
+        assert_eq!(vec![vec![5, 2], vec![5, 3]], stacks);
+        assert_eq!(b"5 ", &interpreter.io().writer()[..]);
+        assert_eq!(&vec![5], interpreter.stack());
     }
 
     #[test]
-    fn interpret_stack_manipulation() {
-        test_program(
-            "1:",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-                (Mode::Execute, vec![1, 1]),
-            ],
-        );
+    fn interpret_speculate_protected() {
+        let reader: &[u8] = &[];
+        let mut io = InputOutput::new(reader, Vec::new());
 
-        test_program(
-            "12\\",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-                (Mode::Execute, vec![1, 2]),
-                (Mode::Execute, vec![2, 1]),
-            ],
-        );
+        io.set_echo(true);
 
-        test_program(
-            "1$",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-                (Mode::Execute, vec![]),
-            ],
+        let mut interpreter = Interpreter::new(Playfield::new("900p@"), io);
+
+        interpreter.protect_region((0, 0), (1, 1));
+
+        let mut speculative = interpreter.speculate();
+
+        assert!(speculative.io().echo());
+        assert_eq!(
+            Err(StepError::ProtectedWrite {
+                pos: (3, 0),
+                target: (0, 0),
+            }),
+            speculative.run_with_limit(10)
         );
+        assert_eq!(b'9', speculative.field()[(0, 0)]);
     }
 
     #[test]
-    fn interpret_output() {
-        test_program(
-            "1.",
-            "",
-            "1 ",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-                (Mode::Execute, vec![]),
-            ],
-        );
+    fn interpret_step_until_change() {
+        let mut interpreter = interpreter(">  v\n   @");
 
-        test_program(
-            "\"a\",25*,",
-            "",
-            "a\n",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Parse, vec![]),
-                (Mode::Parse, vec![0x61]),
-                (Mode::Execute, vec![0x61]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![2]),
-                (Mode::Execute, vec![2, 5]),
-                (Mode::Execute, vec![10]),
-                (Mode::Execute, vec![]),
-            ],
-        );
+        assert_eq!(StepOutcome::Continue, interpreter.step_until_row_change());
+        assert_eq!((3, 1), interpreter.nav().pos());
+        assert_eq!(4, interpreter.steps());
+        assert_eq!(StepOutcome::Terminated, interpreter.step_until_row_change());
+
+        let mut interpreter = self::interpreter("v\n>1@");
+
+        assert_eq!(StepOutcome::Continue, interpreter.step_until_col_change());
+        assert_eq!((1, 1), interpreter.nav().pos());
+
+        let mut interpreter = self::interpreter(">");
+
+        assert_eq!(StepOutcome::Continue, interpreter.step_until_row_change());
+        assert_eq!(STEP_UNTIL_LIMIT, interpreter.steps());
     }
 
     #[test]
-    fn interpret_bridge() {
-        test_program(
-            "#01",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-            ],
-        );
+    fn interpret_predict_branch() {
+        for (program, stack, direction) in [
+            ("_", vec![], Some(Direction::Right)),
+            ("_", vec![3, 0], Some(Direction::Right)),
+            ("_", vec![0, -2], Some(Direction::Left)),
+            ("|", vec![1], Some(Direction::Up)),
+            ("#", vec![1], Some(Direction::Right)),
+            ("?", vec![1], None),
+            (":", vec![1], None),
+        ] {
+            let reader: &[u8] = &[];
+            let io = InputOutput::new(reader, Vec::new());
+            let interpreter = Interpreter::with_stack(Playfield::new(program), io, stack.clone());
+
+            assert_eq!(direction, interpreter.predict_branch());
+            assert_eq!(&stack, interpreter.stack());
+        }
     }
 
     #[test]
-    fn interpret_field_manipulation() {
-        test_program(
-            "30g5",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![3]),
-                (Mode::Execute, vec![3, 0]),
-                (Mode::Execute, vec![0x35]),
-                (Mode::Execute, vec![0x35, 5]),
-            ],
-        );
+    fn interpret_stack_as_string() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let interpreter = Interpreter::with_stack(Playfield::new("@"), io, vec![0x61, 0x62, 0x63]);
 
-        test_program(
-            "77*40p1",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![7]),
-                (Mode::Execute, vec![7, 7]),
-                (Mode::Execute, vec![49]),
-                (Mode::Execute, vec![49, 4]),
-                (Mode::Execute, vec![49, 4, 0]),
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![1]),
-            ],
-        );
+        assert_eq!("cba", interpreter.stack_as_string());
+
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let interpreter = Interpreter::with_stack(Playfield::new("@"), io, vec![0x21, 0x161, -1]);
+
+        assert_eq!("\u{fffd}\u{fffd}!", interpreter.stack_as_string());
     }
 
     #[test]
-    fn interpret_user_input() {
-        test_program(
-            "&",
-            "4\n",
-            "",
-            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![4])],
-        );
+    fn interpret_wrap_count() {
+        let mut interpreter = interpreter("<@1");
 
-        test_program(
-            "&",
-            "abc\n",
-            "",
-            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![0])],
-        );
+        interpreter.run_to_completion();
 
-        test_program(
-            "&",
-            "",
-            "",
-            vec![(Mode::Execute, vec![]), (Mode::Execute, vec![0])],
-        );
+        assert_eq!(&vec![1], interpreter.stack());
+        assert_eq!(1, interpreter.wrap_count());
+    }
 
-        test_program(
-            "~~~",
-            "ab",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![0x61]),
-                (Mode::Execute, vec![0x61, 0x62]),
-                (Mode::Execute, vec![0x61, 0x62, -1]),
-            ],
+    #[test]
+    fn interpret_render_frame() {
+        let mut interpreter = interpreter("78 v\n@  <");
+
+        interpreter.step();
+        interpreter.step();
+
+        let mut frame = Vec::new();
+
+        interpreter.render_frame(&mut frame).unwrap();
+
+        assert_eq!(
+            "78 v\n  ^\n@  <\nstack: [7, 8]\n",
+            String::from_utf8(frame).unwrap()
         );
     }
 
     #[test]
-    fn interpret_termination() {
-        test_program(
-            "@",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Terminate, vec![]),
-                (Mode::Terminate, vec![]),
-            ],
-        );
+    fn interpret_movement_stats() {
+        let mut interpreter = interpreter("> v\n@ <");
 
-        test_program(
-            "5@",
-            "",
-            "",
-            vec![
-                (Mode::Execute, vec![]),
-                (Mode::Execute, vec![5]),
-                (Mode::Terminate, vec![5]),
-                (Mode::Terminate, vec![5]),
-            ],
-        );
+        interpreter.run_to_completion();
+
+        let stats = interpreter.movement_stats();
+
+        assert_eq!(0, stats[Direction::Up.as_u8() as usize]);
+        assert_eq!(1, stats[Direction::Down.as_u8() as usize]);
+        assert_eq!(2, stats[Direction::Left.as_u8() as usize]);
+        assert_eq!(2, stats[Direction::Right.as_u8() as usize]);
+    }
+
+    #[test]
+    fn interpret_discard_ints() {
+        let reader: &[u8] = &[];
+        let mut io = InputOutput::new(reader, Vec::new());
+        io.set_discard_ints(true);
+        io.set_output_encoding(OutputEncoding::Utf8Checked);
+
+        let field = Playfield::new(".,.,@");
+        let mut interpreter = Interpreter::with_stack(field, io, vec![0xa4, 7, 0xc3, 5]);
+
+        interpreter.run_to_completion();
+
+        assert_eq!("ä".as_bytes(), &interpreter.io().writer()[..]);
+        assert!(interpreter.io().discard_ints());
+    }
+
+    #[test]
+    fn interpret_echo_input() {
+        for &(echo, output) in &[(false, "A12 "), (true, "Ax 12\n12 ")] {
+            let mut io = InputOutput::new(&b"x 12\n"[..], Vec::new());
+            io.set_echo(echo);
+
+            let mut interpreter = Interpreter::new(Playfield::new("\"A\",~$&.@"), io);
+
+            interpreter.run_to_completion();
+
+            assert_eq!(output, String::from_utf8_lossy(interpreter.io().writer()));
+        }
     }
 
     #[test]
-    #[should_panic(expected = "Illegal character: x")]
     fn interpret_illegal() {
-        test_program("x", "", "", vec![(Mode::Execute, vec![])]);
+        let mut interpreter = interpreter("1x@");
+
+        assert_eq!(Some(Ok(())), interpreter.try_next());
+
+        let err = StepError::IllegalInstruction {
+            pos: (1, 0),
+            instruction: b'x',
+        };
+
+        assert_eq!(Some(Err(err)), interpreter.try_next());
+        assert_eq!(Some(Err(err)), interpreter.try_next());
+        assert_eq!("Illegal character: x", err.to_string());
+        assert_eq!((1, 0), err.pos());
+        assert_eq!((1, 0), interpreter.nav().pos());
+
+        interpreter.add_no_op(b'x');
+
+        assert_eq!(Some(Ok(())), interpreter.try_next());
+        assert_eq!(None, interpreter.try_next());
+        assert_eq!(None, interpreter.try_next());
     }
 }
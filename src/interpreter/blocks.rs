@@ -0,0 +1,212 @@
+//! Execution grouped into basic blocks
+//!
+//! A basic block is a straight run of steps which ends with a movement or branch instruction,
+//! i.e. an instruction which may change the direction of the instruction pointer, or with the
+//! termination of the program.
+
+use super::{Interpreter, Mode, StepError, StepOutcome};
+use crate::instruction::{InstructionInfo, InstructionKind};
+use std::io::prelude::*;
+
+/// A descriptor of an executed basic block
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BasicBlock {
+    /// The position of the first instruction of the block
+    pub start: (usize, usize),
+    /// The number of steps executed in the block
+    pub len: usize,
+    /// Whether the block ended with a movement or branch instruction
+    pub branch: bool,
+}
+
+/// An iterator which executes an interpreter and yields its basic blocks
+///
+/// A block also ends before a trapped step, so that a trap or breakpoint never lies in the
+/// middle of a block. If a step fails, the steps before it are yielded as a last block and the
+/// error is available from [`error`].
+///
+/// [`error`]: #method.error
+pub struct BasicBlocks<'a, R, W> {
+    interpreter: &'a mut Interpreter<R, W>,
+    error: Option<StepError>,
+}
+
+impl<'a, R, W> BasicBlocks<'a, R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    /// Wrap the given interpreter.
+    pub fn new(interpreter: &'a mut Interpreter<R, W>) -> Self {
+        Self {
+            interpreter,
+            error: None,
+        }
+    }
+
+    /// Return the error of the step which ended the iteration, if any.
+    pub fn error(&self) -> Option<StepError> {
+        self.error
+    }
+
+    fn is_branch(&self, c: u8) -> bool {
+        let c = self.interpreter.resolve(c);
+        let kind = InstructionInfo::lookup(c, self.interpreter.dialect()).map(|i| i.kind);
+
+        matches!(
+            kind,
+            Some(InstructionKind::Movement) | Some(InstructionKind::Branch)
+        )
+    }
+}
+
+impl<'a, R, W> Iterator for BasicBlocks<'a, R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    type Item = BasicBlock;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let start = self.interpreter.nav().pos();
+        let mut len = 0;
+
+        loop {
+            let c = self.interpreter.field()[self.interpreter.nav().pos()];
+            let mode = self.interpreter.mode();
+            let steps = self.interpreter.steps();
+
+            let outcome = match self.interpreter.try_step() {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    self.error = Some(err);
+
+                    return Some(BasicBlock {
+                        start,
+                        len,
+                        branch: false,
+                    })
+                    .filter(|block| block.len > 0);
+                }
+            };
+
+            match outcome {
+                StepOutcome::Continue => {
+                    len += 1;
+
+                    if mode == Mode::Execute && self.is_branch(c) {
+                        return Some(BasicBlock {
+                            start,
+                            len,
+                            branch: true,
+                        });
+                    }
+                }
+                StepOutcome::Trapped(_) if len == 0 => (),
+                StepOutcome::Trapped(_) => {
+                    return Some(BasicBlock {
+                        start,
+                        len,
+                        branch: false,
+                    })
+                }
+                StepOutcome::Terminated if self.interpreter.steps() > steps => {
+                    return Some(BasicBlock {
+                        start,
+                        len: len + 1,
+                        branch: false,
+                    })
+                }
+                StepOutcome::Terminated | StepOutcome::TimedOut => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::InputOutput;
+    use crate::playfield::Playfield;
+
+    #[test]
+    fn basic_blocks() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("5 0_@"), io);
+
+        let blocks: Vec<BasicBlock> = interpreter.basic_blocks().collect();
+
+        assert_eq!(
+            vec![
+                BasicBlock {
+                    start: (0, 0),
+                    len: 4,
+                    branch: true,
+                },
+                BasicBlock {
+                    start: (4, 0),
+                    len: 1,
+                    branch: false,
+                },
+            ],
+            blocks
+        );
+        assert_eq!(&vec![5], interpreter.stack());
+    }
+
+    #[test]
+    fn basic_blocks_trapped() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("123@"), io);
+
+        interpreter.add_breakpoint((2, 0));
+
+        let lens: Vec<usize> = interpreter.basic_blocks().map(|b| b.len).collect();
+
+        assert_eq!(vec![2, 2], lens);
+    }
+
+    #[test]
+    fn basic_blocks_alias() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("5 0A@"), io);
+
+        interpreter.alias(b'A', b'_');
+
+        let branches: Vec<bool> = interpreter.basic_blocks().map(|b| b.branch).collect();
+
+        assert_eq!(vec![true, false], branches);
+    }
+
+    #[test]
+    fn basic_blocks_error() {
+        let reader: &[u8] = &[];
+        let io = InputOutput::new(reader, Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("12x@"), io);
+        let mut blocks = interpreter.basic_blocks();
+
+        assert_eq!(
+            Some(BasicBlock {
+                start: (0, 0),
+                len: 2,
+                branch: false,
+            }),
+            blocks.next()
+        );
+        assert_eq!(None, blocks.next());
+        assert_eq!(
+            Some(StepError::IllegalInstruction {
+                pos: (2, 0),
+                instruction: b'x',
+            }),
+            blocks.error()
+        );
+    }
+}
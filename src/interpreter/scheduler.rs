@@ -0,0 +1,183 @@
+//! Scheduling of several instruction pointers
+//!
+//! Each instruction pointer is driven by its own interpreter. A [`Scheduler`] decides which of
+//! them executes the next step, so tests can force a particular interleaving instead of the
+//! default round-robin order.
+//!
+//! [`Scheduler`]: trait.Scheduler.html
+
+use super::{Interpreter, Mode};
+use std::io::prelude::*;
+
+/// The state of an instruction pointer as seen by a scheduler
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IpState {
+    /// The index of the instruction pointer
+    pub id: usize,
+    /// The current position of the instruction pointer
+    pub pos: (usize, usize),
+    /// The number of steps the instruction pointer has executed so far
+    pub steps: usize,
+}
+
+/// A strategy deciding which instruction pointer steps next
+pub trait Scheduler: Send {
+    /// Return the id of the instruction pointer to step next.
+    ///
+    /// Only instruction pointers which have not terminated are given, ordered by their id. The
+    /// list is never empty and the returned id must be one of the given ones.
+    fn next(&mut self, ips: &[IpState]) -> usize;
+}
+
+/// A scheduler which steps all instruction pointers in turn
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl Scheduler for RoundRobin {
+    fn next(&mut self, ips: &[IpState]) -> usize {
+        let ip = ips.iter().find(|ip| ip.id >= self.next).unwrap_or(&ips[0]);
+
+        self.next = ip.id + 1;
+        ip.id
+    }
+}
+
+/// A set of instruction pointers executed according to a scheduler
+pub struct Concurrent<R, W> {
+    interpreters: Vec<Interpreter<R, W>>,
+    scheduler: Box<dyn Scheduler>,
+}
+
+impl<R, W> Concurrent<R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    /// Create a new set of instruction pointers, which are scheduled round-robin.
+    ///
+    /// The id of each instruction pointer is its index in the given list.
+    pub fn new(interpreters: Vec<Interpreter<R, W>>) -> Self {
+        Self {
+            interpreters,
+            scheduler: Box::new(RoundRobin::default()),
+        }
+    }
+
+    /// Set the scheduler deciding which instruction pointer steps next.
+    pub fn set_scheduler(&mut self, scheduler: Box<dyn Scheduler>) {
+        self.scheduler = scheduler;
+    }
+
+    /// Get the interpreters driving the instruction pointers.
+    pub fn interpreters(&self) -> &[Interpreter<R, W>] {
+        &self.interpreters
+    }
+
+    /// Execute a single step of the instruction pointer chosen by the scheduler.
+    ///
+    /// Returns the id of the instruction pointer or `None` if all of them have terminated.
+    pub fn step(&mut self) -> Option<usize> {
+        let ips: Vec<IpState> = self
+            .interpreters
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.mode() != Mode::Terminate)
+            .map(|(id, i)| IpState {
+                id,
+                pos: i.nav().pos(),
+                steps: i.steps(),
+            })
+            .collect();
+
+        if ips.is_empty() {
+            return None;
+        }
+
+        let id = self.scheduler.next(&ips);
+
+        self.interpreters[id].step();
+
+        Some(id)
+    }
+
+    /// Execute steps until all instruction pointers have terminated.
+    pub fn run_to_completion(&mut self) {
+        while self.step().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::InputOutput;
+    use crate::playfield::Playfield;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct LowestFirst;
+
+    impl Scheduler for LowestFirst {
+        fn next(&mut self, ips: &[IpState]) -> usize {
+            ips[0].id
+        }
+    }
+
+    fn concurrent(writer: &SharedWriter) -> Concurrent<&'static [u8], SharedWriter> {
+        let interpreters = ["1.2.@", "3.4.@"]
+            .iter()
+            .map(|p| {
+                let reader: &[u8] = &[];
+                let io = InputOutput::new(reader, writer.clone());
+
+                Interpreter::new(Playfield::new(p), io)
+            })
+            .collect();
+
+        Concurrent::new(interpreters)
+    }
+
+    #[test]
+    fn round_robin() {
+        let writer = SharedWriter::default();
+        let mut concurrent = concurrent(&writer);
+
+        assert_eq!(Some(0), concurrent.step());
+        assert_eq!(Some(1), concurrent.step());
+        assert_eq!(Some(0), concurrent.step());
+
+        concurrent.run_to_completion();
+
+        assert_eq!(None, concurrent.step());
+        assert_eq!(b"1 3 2 4 ", &writer.0.lock().unwrap()[..]);
+    }
+
+    #[test]
+    fn custom_scheduler() {
+        let writer = SharedWriter::default();
+        let mut concurrent = concurrent(&writer);
+
+        concurrent.set_scheduler(Box::new(LowestFirst));
+        concurrent.run_to_completion();
+
+        assert_eq!(b"1 2 3 4 ", &writer.0.lock().unwrap()[..]);
+        assert!(concurrent
+            .interpreters()
+            .iter()
+            .all(|i| i.mode() == Mode::Terminate));
+    }
+}
@@ -0,0 +1,105 @@
+//! Stack manipulation operations
+//!
+//! All operations follow the Befunge-93 semantics for an empty stack: popping from an empty
+//! stack yields zero. The `clear` operation corresponds to the Funge-98 `n` instruction.
+
+use super::Stack;
+
+/// Pop a value from the stack, returning zero if the stack is empty.
+pub fn pop(stack: &mut Stack) -> i64 {
+    stack.pop().unwrap_or(0)
+}
+
+/// Duplicate the value on top of the stack.
+///
+/// Duplicating an empty stack pushes two zeros.
+pub fn dup(stack: &mut Stack) {
+    let v = pop(stack);
+
+    stack.push(v);
+    stack.push(v);
+}
+
+/// Swap the two values on top of the stack.
+///
+/// Missing values are treated as zeros.
+pub fn swap(stack: &mut Stack) {
+    let a = pop(stack);
+    let b = pop(stack);
+
+    stack.push(a);
+    stack.push(b);
+}
+
+/// Pop the value on top of the stack and discard it.
+pub fn drop(stack: &mut Stack) {
+    stack.pop();
+}
+
+/// Remove all values from the stack.
+pub fn clear(stack: &mut Stack) {
+    stack.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stack_pop() {
+        let mut stack = vec![1, 2];
+
+        assert_eq!(2, pop(&mut stack));
+        assert_eq!(1, pop(&mut stack));
+        assert_eq!(0, pop(&mut stack));
+        assert_eq!(Vec::<i64>::new(), stack);
+    }
+
+    #[test]
+    fn stack_dup() {
+        let mut stack = vec![];
+        dup(&mut stack);
+        assert_eq!(vec![0, 0], stack);
+
+        let mut stack = vec![1, 2];
+        dup(&mut stack);
+        assert_eq!(vec![1, 2, 2], stack);
+    }
+
+    #[test]
+    fn stack_swap() {
+        let mut stack = vec![];
+        swap(&mut stack);
+        assert_eq!(vec![0, 0], stack);
+
+        let mut stack = vec![1];
+        swap(&mut stack);
+        assert_eq!(vec![1, 0], stack);
+
+        let mut stack = vec![1, 2, 3];
+        swap(&mut stack);
+        assert_eq!(vec![1, 3, 2], stack);
+    }
+
+    #[test]
+    fn stack_drop() {
+        let mut stack = vec![];
+        drop(&mut stack);
+        assert_eq!(Vec::<i64>::new(), stack);
+
+        let mut stack = vec![1, 2];
+        drop(&mut stack);
+        assert_eq!(vec![1], stack);
+    }
+
+    #[test]
+    fn stack_clear() {
+        let mut stack = vec![];
+        clear(&mut stack);
+        assert_eq!(Vec::<i64>::new(), stack);
+
+        let mut stack = vec![1, 2, 3];
+        clear(&mut stack);
+        assert_eq!(Vec::<i64>::new(), stack);
+    }
+}
@@ -0,0 +1,284 @@
+//! Persistable execution traces
+//!
+//! A [`TraceRecorder`] writes one record per executed step to a binary stream, which can be
+//! read back with a [`TracePlayer`] without running the program again.
+//!
+//! Each record consists of a length byte followed by the payload: the x and y coordinates as
+//! little-endian 32 bit integers, the executed byte and the side effect of the step.
+//!
+//! A [`TapeRecorder`] only keeps the executed bytes in memory, which allows comparing the
+//! execution of programs with a different layout.
+//!
+//! [`TraceRecorder`]: struct.TraceRecorder.html
+//! [`TracePlayer`]: struct.TracePlayer.html
+//! [`TapeRecorder`]: struct.TapeRecorder.html
+
+use super::{Interpreter, Mode, StepEffect, StepOutcome};
+use crate::instruction::instr;
+use std::convert::TryFrom;
+use std::io;
+use std::io::prelude::*;
+
+const RECORD_LEN: u8 = 10;
+
+/// A single executed step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceEvent {
+    /// The position of the executed cell
+    pub pos: (usize, usize),
+    /// The byte stored in the executed cell
+    pub instruction: u8,
+    /// The side effect of the step
+    pub effect: StepEffect,
+}
+
+/// A writer of execution traces
+pub struct TraceRecorder<W> {
+    writer: W,
+}
+
+impl<W: Write> TraceRecorder<W> {
+    /// Create a new recorder writing to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a single event.
+    ///
+    /// Fails with `InvalidInput` if a coordinate does not fit into 32 bits.
+    pub fn record(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let coord = |c: usize| {
+            u32::try_from(c).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "coordinate"))
+        };
+
+        let mut buf = Vec::with_capacity(1 + RECORD_LEN as usize);
+
+        buf.push(RECORD_LEN);
+        buf.extend_from_slice(&coord(event.pos.0)?.to_le_bytes());
+        buf.extend_from_slice(&coord(event.pos.1)?.to_le_bytes());
+        buf.push(event.instruction);
+        buf.push(effect_to_u8(event.effect));
+
+        self.writer.write_all(&buf)
+    }
+
+    /// Execute a single step of the given interpreter and record it.
+    ///
+    /// Trapped steps and steps after termination execute nothing and are not recorded.
+    pub fn step<R, V>(&mut self, interpreter: &mut Interpreter<R, V>) -> io::Result<StepOutcome>
+    where
+        R: BufRead,
+        V: Write,
+    {
+        let pos = interpreter.nav().pos();
+        let instruction = interpreter.field()[pos];
+        let steps = interpreter.steps();
+
+        let outcome = interpreter.step();
+
+        if interpreter.steps() > steps {
+            self.record(&TraceEvent {
+                pos,
+                instruction,
+                effect: interpreter.last_effect(),
+            })?;
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// A recorder of the flat sequence of executed bytes
+///
+/// Positions are not recorded and neither are executed spaces and arrows, as both only depend
+/// on the layout of the program. Characters pushed in string mode are recorded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TapeRecorder {
+    tape: Vec<u8>,
+}
+
+impl TapeRecorder {
+    /// Create a new recorder with an empty tape.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the recorded bytes.
+    pub fn tape(&self) -> &[u8] {
+        &self.tape
+    }
+
+    /// Return the recorded bytes as a string, replacing invalid UTF-8.
+    pub fn tape_string(&self) -> String {
+        String::from_utf8_lossy(&self.tape).into_owned()
+    }
+
+    /// Execute a single step of the given interpreter and record it.
+    ///
+    /// Trapped steps and steps after termination execute nothing and are not recorded.
+    pub fn step<R, W>(&mut self, interpreter: &mut Interpreter<R, W>) -> StepOutcome
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let instruction = interpreter.field()[interpreter.nav().pos()];
+        let mode = interpreter.mode();
+        let steps = interpreter.steps();
+
+        let outcome = interpreter.step();
+
+        let layout = matches!(
+            instruction,
+            instr::SPACE | instr::RIGHT | instr::LEFT | instr::UP | instr::DOWN
+        );
+
+        if interpreter.steps() > steps && !(mode == Mode::Execute && layout) {
+            self.tape.push(instruction);
+        }
+
+        outcome
+    }
+}
+
+/// A reader of execution traces
+///
+/// The player iterates over the recorded events. Records with unknown data are reported as
+/// `InvalidData` errors.
+pub struct TracePlayer<R> {
+    reader: R,
+}
+
+impl<R: Read> TracePlayer<R> {
+    /// Create a new player reading from the given reader.
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_event(&mut self) -> io::Result<Option<TraceEvent>> {
+        let mut len = [0; 1];
+
+        if self.reader.read(&mut len)? == 0 {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0; len[0] as usize];
+
+        self.reader.read_exact(&mut buf)?;
+
+        if buf.len() < RECORD_LEN as usize {
+            return Err(invalid_data("truncated record"));
+        }
+
+        let coord = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+
+        Ok(Some(TraceEvent {
+            pos: (coord(&buf[0..4]), coord(&buf[4..8])),
+            instruction: buf[8],
+            effect: effect_from_u8(buf[9]).ok_or_else(|| invalid_data("unknown effect"))?,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for TracePlayer<R> {
+    type Item = io::Result<TraceEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_event().transpose()
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn effect_to_u8(effect: StepEffect) -> u8 {
+    match effect {
+        StepEffect::None => 0,
+        StepEffect::OutputWritten => 1,
+        StepEffect::InputRead => 2,
+        StepEffect::FieldModified => 3,
+        StepEffect::InputDefaulted => 4,
+    }
+}
+
+fn effect_from_u8(val: u8) -> Option<StepEffect> {
+    match val {
+        0 => Some(StepEffect::None),
+        1 => Some(StepEffect::OutputWritten),
+        2 => Some(StepEffect::InputRead),
+        3 => Some(StepEffect::FieldModified),
+        4 => Some(StepEffect::InputDefaulted),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::NullInputOutput;
+    use crate::playfield::Playfield;
+
+    #[test]
+    fn trace_round_trip() {
+        let mut interpreter =
+            Interpreter::new(Playfield::new("5.v\n  @"), NullInputOutput::default());
+        let mut recorder = TraceRecorder::new(Vec::new());
+
+        while recorder.step(&mut interpreter).unwrap() == StepOutcome::Continue {}
+
+        let bytes = recorder.into_inner();
+
+        assert_eq!(4 * 11, bytes.len());
+
+        let events: Vec<TraceEvent> = TracePlayer::new(&bytes[..]).map(Result::unwrap).collect();
+        let event = |pos, instruction, effect| TraceEvent {
+            pos,
+            instruction,
+            effect,
+        };
+
+        assert_eq!(
+            vec![
+                event((0, 0), b'5', StepEffect::None),
+                event((1, 0), b'.', StepEffect::OutputWritten),
+                event((2, 0), b'v', StepEffect::None),
+                event((2, 1), b'@', StepEffect::None),
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn tape_layout() {
+        let tape = |program| {
+            let mut interpreter =
+                Interpreter::new(Playfield::new(program), NullInputOutput::default());
+            let mut recorder = TapeRecorder::new();
+
+            while recorder.step(&mut interpreter) == StepOutcome::Continue {}
+
+            recorder.tape_string()
+        };
+
+        assert_eq!("23*@", tape("23*@"));
+        assert_eq!(tape("23*@"), tape("v\n2\n3\n*\n@"));
+        assert_eq!(tape("23*@"), tape(" 2 v\n@*3<"));
+        assert_eq!("\"a \"@", tape("\"a \"@"));
+    }
+
+    #[test]
+    fn trace_invalid() {
+        let mut player = TracePlayer::new(&[10, 0, 0, 0, 0, 0, 0, 0, 0, b'@', 9][..]);
+
+        assert!(player.next().unwrap().is_err());
+
+        let mut player = TracePlayer::new(&[10, 0, 0][..]);
+
+        assert!(player.next().unwrap().is_err());
+    }
+}
@@ -1,7 +1,9 @@
 //! A Befunge interpreter written in Rust
 //!
 //! The [`playfield`] module provides all functionality to navigate through a Befunge program
-//! and the [`interpreter`] module implements roughly the [Befunge-93 semantics].
+//! and the [`interpreter`] module implements roughly the [Befunge-93 semantics]. The
+//! [`instruction`] module describes the available instructions. Untrusted programs can be run
+//! with [`run_sandboxed`] and programs can be tested with the [`testing`] module.
 //!
 //! # Example
 //!
@@ -19,9 +21,17 @@
 //!
 //! [`playfield`]: playfield/index.html
 //! [`interpreter`]: interpreter/index.html
+//! [`instruction`]: instruction/index.html
+//! [`run_sandboxed`]: fn.run_sandboxed.html
+//! [`testing`]: testing/index.html
 //! [Befunge-93 semantics]: https://en.wikipedia.org/wiki/Befunge#Befunge-93_instruction_list
 
 extern crate rand;
 
+pub mod instruction;
 pub mod interpreter;
 pub mod playfield;
+pub mod sandbox;
+pub mod testing;
+
+pub use crate::sandbox::{run_sandboxed, SandboxOutcome};
@@ -1,14 +1,162 @@
+use crate::instruction::{instr, InstructionKind};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
 use std::iter;
+use std::mem;
 use std::ops;
+use std::path::Path;
 use std::str;
+use std::string;
 
-/// A two-dimensional matrix of characters
+/// Options to control how a playfield is loaded
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    /// Expand tabs to the next multiple of this many columns.
+    ///
+    /// If `None`, tabs are kept as single cells.
+    pub tab_width: Option<usize>,
+}
+
+/// An error while loading a program from a file
 #[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be opened.
+    Open(io::Error),
+    /// The file could be opened, but not read.
+    Read(io::Error),
+    /// The file is no valid UTF-8.
+    Utf8(string::FromUtf8Error),
+    /// The program has `len` bytes, but at most `max_size` are allowed.
+    TooLarge { len: u64, max_size: u64 },
+}
+
+impl LoadError {
+    /// Describe the error for the file at the given path, e.g. "could not open a.bf: ...".
+    pub fn describe<P: AsRef<Path>>(&self, path: P) -> String {
+        let path = path.as_ref().display();
+
+        match self {
+            LoadError::Open(e) => format!("could not open {}: {}", path, e),
+            LoadError::Read(e) => format!("could not read {}: {}", path, e),
+            LoadError::Utf8(_) | LoadError::TooLarge { .. } => format!("{}: {}", path, self),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Open(e) => write!(f, "could not open: {}", e),
+            LoadError::Read(e) => write!(f, "could not read: {}", e),
+            LoadError::Utf8(e) => write!(f, "invalid UTF-8: {}", e),
+            LoadError::TooLarge { len, max_size } => write!(
+                f,
+                "program too large: {} bytes, but at most {} are allowed",
+                len, max_size
+            ),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Open(e) | LoadError::Read(e) => Some(e),
+            LoadError::Utf8(e) => Some(e),
+            LoadError::TooLarge { .. } => None,
+        }
+    }
+}
+
+/// Check the length of a program in bytes against the maximum size, if any.
+pub fn check_size(len: u64, max_size: Option<u64>) -> Result<(), LoadError> {
+    match max_size {
+        Some(max_size) if len > max_size => Err(LoadError::TooLarge { len, max_size }),
+        _ => Ok(()),
+    }
+}
+
+/// Read the program from the file at the given path.
+///
+/// Files with more than `max_size` bytes are rejected before they are read. As not every file
+/// reports its length, at most one byte more than `max_size` is read in any case.
+pub fn read_program<P: AsRef<Path>>(path: P, max_size: Option<u64>) -> Result<String, LoadError> {
+    let file = File::open(path).map_err(LoadError::Open)?;
+    let mut contents = Vec::new();
+
+    check_size(file.metadata().map_err(LoadError::Read)?.len(), max_size)?;
+
+    let limit = max_size.map_or(u64::MAX, |max_size| max_size.saturating_add(1));
+    file.take(limit)
+        .read_to_end(&mut contents)
+        .map_err(LoadError::Read)?;
+
+    check_size(contents.len() as u64, max_size)?;
+
+    String::from_utf8(contents).map_err(LoadError::Utf8)
+}
+
+/// A single edit of a playfield cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edit {
+    pub pos: (usize, usize),
+    pub old: u8,
+    pub new: u8,
+}
+
+/// The type of the values stored in the cells of a playfield
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CellType {
+    /// Cells hold a single byte, as in Befunge-93.
+    #[default]
+    Byte,
+    /// Cells hold a full integer, as in Funge-98.
+    ///
+    /// The byte of a cell is the value truncated to a byte, so executing and displaying the
+    /// playfield still work on bytes.
+    Wide,
+}
+
+/// The value read from padding cells
+///
+/// Padding cells are the cells which have never been written, i.e. the cells which pad the
+/// lines of the input to the width of the playfield and the cells added by resizing it. They
+/// always hold a space, which is executed as a no-op. Positions outside of the playfield are
+/// no padding cells, `g` reads them as zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PaddingValue {
+    /// Padding cells read as a space, as in Befunge-93.
+    #[default]
+    Space,
+    /// Padding cells read as zero, like unwritten Funge-space in some Funge-98 interpreters.
+    Zero,
+}
+
+impl PaddingValue {
+    /// Return the value a padding cell reads as.
+    pub fn value(self) -> i64 {
+        match self {
+            PaddingValue::Space => i64::from(b' '),
+            PaddingValue::Zero => 0,
+        }
+    }
+}
+
+/// A two-dimensional matrix of characters
+#[derive(Clone, Debug, PartialEq)]
 pub struct Playfield {
     field: Vec<u8>,
     width: usize,
     height: usize,
+    edits: Option<Vec<Edit>>,
+    wide: Option<HashMap<(usize, usize), i64>>,
+    padded: Vec<bool>,
+    padding: PaddingValue,
 }
 
 impl Playfield {
@@ -16,25 +164,106 @@ impl Playfield {
     ///
     /// Each line in the input is padded with spaces to the length of the longest line.
     /// Width and height are defined as the length of the longest line and the number of lines in
-    /// the input string.
+    /// the input string. A single trailing newline does not start a new line, but further empty
+    /// lines are kept as rows of spaces. The playfield is at least 1×1, so an empty input yields a
+    /// single space.
     pub fn new(input: &str) -> Self {
-        let lines: Vec<&str> = input.lines().collect();
-        let width = lines.iter().map(|s| s.bytes().count()).max().unwrap();
-        let height = lines.len();
+        Self::new_with_options(input, &LoadOptions::default())
+    }
 
+    /// Create a new playfield from the given input string using the given options.
+    ///
+    /// Tabs are expanded line by line before the width of the playfield is computed.
+    ///
+    /// The input is scanned twice: once to determine the dimensions and once to fill the
+    /// playfield, which is allocated exactly once.
+    pub fn new_with_options(input: &str, options: &LoadOptions) -> Self {
+        let tab_width = options.tab_width;
+
+        let (width, height) = input.lines().fold((1, 0), |(width, height), l| {
+            (width.max(expand_tabs(l, tab_width).count()), height + 1)
+        });
+
+        let height = height.max(1);
         let mut field = Vec::with_capacity(width * height);
+        let mut padded = Vec::with_capacity(width * height);
+
+        for l in input.lines() {
+            let start = field.len();
+
+            field.extend(expand_tabs(l, tab_width));
 
-        for l in lines {
-            field.extend(l.bytes().chain(iter::repeat(b' ')).take(width));
+            let len = field.len() - start;
+
+            field.extend(iter::repeat(b' ').take(width - len));
+            padded.extend(
+                iter::repeat(false)
+                    .take(len)
+                    .chain(iter::repeat(true).take(width - len)),
+            );
         }
 
+        field.resize(width * height, b' ');
+        padded.resize(width * height, true);
+
         Self {
             field,
             width,
             height,
+            edits: None,
+            wide: None,
+            padded,
+            padding: PaddingValue::Space,
         }
     }
 
+    /// Create a new playfield of the given dimensions, filling each cell `(x, y)` with `f(x, y)`.
+    ///
+    /// The dimensions are at least 1×1. No cell is considered padding.
+    pub fn generate<F: Fn(usize, usize) -> u8>(width: usize, height: usize, f: F) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+
+        Self {
+            field: (0..width * height)
+                .map(|i| f(i % width, i / width))
+                .collect(),
+            width,
+            height,
+            edits: None,
+            wide: None,
+            padded: vec![false; width * height],
+            padding: PaddingValue::Space,
+        }
+    }
+
+    /// Create a new playfield from the contents of the given reader.
+    ///
+    /// The whole input is read before the playfield is created.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut contents = String::new();
+
+        reader.read_to_string(&mut contents)?;
+
+        Ok(Self::new(&contents))
+    }
+
+    /// Create a new playfield from the contents of the file at the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Create a new playfield from the file at the given path with at most `max_size` bytes.
+    ///
+    /// See [`read_program`] for how the size is checked.
+    ///
+    /// [`read_program`]: fn.read_program.html
+    pub fn from_path_with_limit<P: AsRef<Path>>(
+        path: P,
+        max_size: Option<u64>,
+    ) -> Result<Self, LoadError> {
+        read_program(path, max_size).map(|contents| Self::new(&contents))
+    }
+
     /// Return the width of this playfield.
     pub fn width(&self) -> usize {
         self.width
@@ -47,13 +276,378 @@ impl Playfield {
 
     /// Return the dimensions of this playfield.
     pub fn dimensions(&self) -> (usize, usize) {
-        (self.width, self.height)
+        (self.width(), self.height())
     }
 
     /// Return an iterator over the lines of this playfield.
     pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
         self.field.chunks(self.width)
     }
+
+    /// Set the cell at the given position to the given value.
+    ///
+    /// If the edit log is enabled, the edit is recorded. Assigning through the index operator
+    /// bypasses the edit log.
+    pub fn set(&mut self, pos: (usize, usize), val: u8) {
+        let old = self[pos];
+
+        if let Some(edits) = &mut self.edits {
+            edits.push(Edit { pos, old, new: val });
+        }
+
+        self[pos] = val;
+    }
+
+    /// Return the type of the cells of this playfield.
+    pub fn cell_type(&self) -> CellType {
+        match self.wide {
+            Some(_) => CellType::Wide,
+            None => CellType::Byte,
+        }
+    }
+
+    /// Set the type of the cells of this playfield.
+    ///
+    /// Switching to byte cells truncates all values to their bytes.
+    pub fn set_cell_type(&mut self, cell_type: CellType) {
+        self.wide = match cell_type {
+            CellType::Byte => None,
+            CellType::Wide => self.wide.take().or_else(|| Some(HashMap::new())),
+        };
+    }
+
+    /// Return the value read from padding cells.
+    pub fn padding_value(&self) -> PaddingValue {
+        self.padding
+    }
+
+    /// Set the value read from padding cells. Padding cells read as spaces by default.
+    pub fn set_padding_value(&mut self, padding: PaddingValue) {
+        self.padding = padding;
+    }
+
+    /// Check whether the cell at the given position has never been written.
+    pub fn is_padding(&self, pos: (usize, usize)) -> bool {
+        self.padded[pos.0 + self.width * pos.1]
+    }
+
+    /// Return the value of the cell at the given position.
+    ///
+    /// For byte cells, this is the byte of the cell. Padding cells read as their
+    /// [`PaddingValue`].
+    ///
+    /// [`PaddingValue`]: enum.PaddingValue.html
+    pub fn value(&self, pos: (usize, usize)) -> i64 {
+        match self.wide.as_ref().and_then(|wide| wide.get(&pos)) {
+            Some(&val) => val,
+            None if self.is_padding(pos) => self.padding.value(),
+            None => i64::from(self[pos]),
+        }
+    }
+
+    /// Set the cell at the given position to the given value.
+    ///
+    /// For byte cells, the value is truncated to a byte. Like assigning through the index
+    /// operator, this bypasses the edit log.
+    pub fn set_value(&mut self, pos: (usize, usize), val: i64) {
+        self[pos] = val as u8;
+
+        if let Some(wide) = &mut self.wide {
+            if !(0..=255).contains(&val) {
+                wide.insert(pos, val);
+            }
+        }
+    }
+
+    /// Set the cell at `(x, y)` to the given value, growing the playfield if necessary.
+    ///
+    /// Rows and columns added to reach the position are padded with spaces, but the playfield
+    /// never grows beyond `max_cells` cells. Negative coordinates and positions which would
+    /// exceed the limit are rejected and leave the playfield unchanged. Returns the position of
+    /// the written cell, or `None` if nothing has been written.
+    pub fn put(&mut self, x: i64, y: i64, val: i64, max_cells: usize) -> Option<(usize, usize)> {
+        let pos = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        let width = self.width.max(pos.0.checked_add(1)?);
+        let height = self.height.max(pos.1.checked_add(1)?);
+
+        if (width, height) != self.dimensions() {
+            if width.checked_mul(height)? > max_cells {
+                return None;
+            }
+
+            self.resize(width, height);
+        }
+
+        self.set_value(pos, val);
+
+        Some(pos)
+    }
+
+    /// Exchange the cells at the given positions.
+    ///
+    /// Both edits are recorded in the edit log. Swapping a cell with itself does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position lies outside of the playfield.
+    pub fn swap_cells(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let (width, height) = self.dimensions();
+
+        assert!(
+            a.0 < width && a.1 < height && b.0 < width && b.1 < height,
+            "cannot swap {:?} and {:?} in a {}x{} playfield",
+            a,
+            b,
+            width,
+            height
+        );
+
+        if a == b {
+            return;
+        }
+
+        let (val_a, val_b) = (self.value(a), self.value(b));
+        let (byte_a, byte_b) = (self[a], self[b]);
+
+        self.set(a, byte_b);
+        self.set(b, byte_a);
+
+        if let Some(wide) = &mut self.wide {
+            for &(pos, val) in &[(a, val_b), (b, val_a)] {
+                if !(0..=255).contains(&val) {
+                    wide.insert(pos, val);
+                }
+            }
+        }
+    }
+
+    /// Enable or disable the edit log.
+    ///
+    /// Disabling the edit log discards all recorded edits.
+    pub fn set_edit_log(&mut self, enabled: bool) {
+        self.edits = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Drain all edits recorded since the last call.
+    ///
+    /// Returns an empty list if the edit log is disabled.
+    pub fn take_edits(&mut self) -> Vec<Edit> {
+        match &mut self.edits {
+            Some(edits) => mem::take(edits),
+            None => Vec::new(),
+        }
+    }
+
+    /// Read the rectangular region with the top left corner at `(x, y)` and the given size.
+    ///
+    /// The region is clamped to the bounds of the playfield, i.e. rows and columns which lie
+    /// outside of the playfield are omitted from the result.
+    pub fn read_region(&self, x: usize, y: usize, w: usize, h: usize) -> Vec<Vec<u8>> {
+        if x >= self.width {
+            return Vec::new();
+        }
+
+        let x_end = x.saturating_add(w).min(self.width);
+        let y_end = y.saturating_add(h).min(self.height);
+
+        (y..y_end)
+            .map(|y| self.field[x + self.width * y..x_end + self.width * y].to_vec())
+            .collect()
+    }
+
+    /// Write the given rows into the playfield with the top left corner at `(x, y)`.
+    ///
+    /// Rows may have different lengths. Cells which would lie outside of the playfield are
+    /// silently dropped. Each written cell is recorded in the edit log.
+    pub fn write_region(&mut self, x: usize, y: usize, region: &[Vec<u8>]) {
+        for (dy, row) in region
+            .iter()
+            .enumerate()
+            .take(self.height.saturating_sub(y))
+        {
+            for (dx, &val) in row.iter().enumerate().take(self.width.saturating_sub(x)) {
+                self.set((x + dx, y + dy), val);
+            }
+        }
+    }
+
+    /// Resize this playfield to the given dimensions.
+    ///
+    /// New cells are filled with spaces and cells outside of the new dimensions are discarded.
+    /// The dimensions are at least 1×1. Resizing is not recorded in the edit log.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut field = Vec::with_capacity(width * height);
+        let mut padded = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            match self.lines().nth(y) {
+                Some(l) => field.extend(l.iter().cloned().chain(iter::repeat(b' ')).take(width)),
+                None => field.extend(iter::repeat(b' ').take(width)),
+            }
+
+            match self.padded.chunks(self.width).nth(y) {
+                Some(l) => padded.extend(l.iter().cloned().chain(iter::repeat(true)).take(width)),
+                None => padded.extend(iter::repeat(true).take(width)),
+            }
+        }
+
+        self.field = field;
+        self.padded = padded;
+        self.width = width;
+        self.height = height;
+
+        if let Some(wide) = &mut self.wide {
+            wide.retain(|&(x, y), _| x < width && y < height);
+        }
+    }
+
+    /// Apply the given `(x, y, value)` edits in order.
+    ///
+    /// If `grow` is set, the playfield is first resized once to fit all edits. Otherwise, edits
+    /// outside of the playfield are dropped. Each applied edit is recorded in the edit log.
+    pub fn apply_patch(&mut self, edits: &[(usize, usize, u8)], grow: bool) {
+        if grow {
+            let (width, height) = edits.iter().fold(self.dimensions(), |(w, h), &(x, y, _)| {
+                (w.max(x + 1), h.max(y + 1))
+            });
+
+            if (width, height) != self.dimensions() {
+                self.resize(width, height);
+            }
+        }
+
+        for &(x, y, val) in edits {
+            if x < self.width && y < self.height {
+                self.set((x, y), val);
+            }
+        }
+    }
+
+    /// Count the non-space cells of this playfield by the kind of their Befunge-93 instruction.
+    ///
+    /// This is a static summary of the program. Cells within string literals are counted
+    /// like any other cell, since telling code from data would require executing the program.
+    pub fn instruction_histogram(&self) -> HashMap<InstructionKind, usize> {
+        let mut histogram = HashMap::new();
+
+        for &c in self.field.iter().filter(|&&c| c != b' ') {
+            *histogram.entry(InstructionKind::of(c)).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    /// Check whether this program statically contains no nondeterministic instructions.
+    ///
+    /// Returns `false` if any cell holds `?`, `&` or `~`, so the output may depend on chance or
+    /// on the input. Like [`instruction_histogram`], this is a static check: cells within string
+    /// literals count as well, and instructions which are only written by `p` at runtime are
+    /// not detected, so self-modifying programs may be reported as deterministic.
+    ///
+    /// [`instruction_histogram`]: #method.instruction_histogram
+    pub fn is_deterministic(&self) -> bool {
+        !self.field.iter().any(|&c| {
+            matches!(
+                c,
+                instr::RANDOM | instr::INPUT_INTEGER | instr::INPUT_CHARACTER
+            )
+        })
+    }
+
+    /// Return the number of non-space cells.
+    pub fn nonspace_count(&self) -> usize {
+        self.field.iter().filter(|&&c| c != b' ').count()
+    }
+
+    /// Return the share of non-space cells among all cells, from `0.0` to `1.0`.
+    ///
+    /// An empty playfield has a density of `0.0`.
+    pub fn density(&self) -> f64 {
+        if self.field.is_empty() {
+            0.0
+        } else {
+            self.nonspace_count() as f64 / self.field.len() as f64
+        }
+    }
+
+    /// Return the minimal bounding box of all non-space cells.
+    ///
+    /// The box is given by its top left and bottom right corners, both inclusive. Returns `None`
+    /// if the playfield only consists of spaces.
+    pub fn content_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let mut bounds: Option<((usize, usize), (usize, usize))> = None;
+
+        for (y, line) in self.lines().enumerate() {
+            for (x, _) in line.iter().enumerate().filter(|(_, &c)| c != b' ') {
+                bounds = Some(match bounds {
+                    Some(((x0, y0), (x1, y1))) => ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y))),
+                    None => ((x, y), (x, y)),
+                });
+            }
+        }
+
+        bounds
+    }
+
+    /// Render this playfield like its `Display` implementation with the given position marked.
+    ///
+    /// The row containing the position is followed by a line with a `^` below the position.
+    pub fn render_with_cursor(&self, pos: (usize, usize)) -> String {
+        let mut out = String::new();
+
+        for (y, l) in self.lines().enumerate() {
+            out.push_str(&String::from_utf8_lossy(l));
+            out.push('\n');
+
+            if y == pos.1 {
+                out.extend(iter::repeat(' ').take(pos.0));
+                out.push_str("^\n");
+            }
+        }
+
+        out
+    }
+
+    /// Return a copy of this playfield cropped to the bounding box of its non-space cells.
+    ///
+    /// The origin of the copy is the top left corner of the bounding box, so positions in the
+    /// copy do not correspond to positions in the original playfield. This is meant for display
+    /// and export, not for running the program again. A playfield which only consists of spaces
+    /// is trimmed to a single space. The edit log of the copy is disabled and its cells are bytes.
+    pub fn trimmed(&self) -> Playfield {
+        let (x, y, width, height) = match self.content_bounds() {
+            Some(((x0, y0), (x1, y1))) => (x0, y0, x1 - x0 + 1, y1 - y0 + 1),
+            None => (0, 0, 1, 1),
+        };
+
+        Self {
+            field: self.read_region(x, y, width, height).concat(),
+            width,
+            height,
+            edits: None,
+            wide: None,
+            padded: (y..y + height)
+                .flat_map(|y| &self.padded[x + self.width * y..x + width + self.width * y])
+                .cloned()
+                .collect(),
+            padding: self.padding,
+        }
+    }
+}
+
+fn expand_tabs(line: &str, tab_width: Option<usize>) -> impl Iterator<Item = u8> + '_ {
+    let mut col = 0;
+
+    line.bytes().flat_map(move |b| {
+        let (b, n) = match (b, tab_width) {
+            (b'\t', Some(w)) if w > 0 => (b' ', w - col % w),
+            _ => (b, 1),
+        };
+
+        col += n;
+        iter::repeat(b).take(n)
+    })
 }
 
 impl ops::Index<(usize, usize)> for Playfield {
@@ -66,6 +660,12 @@ impl ops::Index<(usize, usize)> for Playfield {
 
 impl ops::IndexMut<(usize, usize)> for Playfield {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        if let Some(wide) = &mut self.wide {
+            wide.remove(&index);
+        }
+
+        self.padded[index.0 + self.width * index.1] = false;
+
         &mut self.field[index.0 + self.width * index.1]
     }
 }
@@ -73,7 +673,7 @@ impl ops::IndexMut<(usize, usize)> for Playfield {
 impl fmt::Display for Playfield {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for l in self.lines() {
-            writeln!(f, "{}", str::from_utf8(l).unwrap())?;
+            writeln!(f, "{}", String::from_utf8_lossy(l))?;
         }
 
         Ok(())
@@ -89,13 +689,94 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    /// Return a stable byte representation of this direction.
+    ///
+    /// The mapping is `0 = Up`, `1 = Down`, `2 = Left` and `3 = Right`.
+    pub fn as_u8(self) -> u8 {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+
+    /// Create a direction from its byte representation.
+    ///
+    /// Returns `None` if the byte does not represent a direction.
+    pub fn from_u8(val: u8) -> Option<Self> {
+        match val {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Down),
+            2 => Some(Direction::Left),
+            3 => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Return the unit vector pointing in this direction.
+    ///
+    /// The y axis points downwards, so moving up decreases the y coordinate.
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    /// Create a direction from a unit vector.
+    ///
+    /// Returns `None` if the vector does not point in one of the four cardinal directions.
+    pub fn from_delta(delta: (i64, i64)) -> Option<Self> {
+        match delta {
+            (0, -1) => Some(Direction::Up),
+            (0, 1) => Some(Direction::Down),
+            (-1, 0) => Some(Direction::Left),
+            (1, 0) => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Return an arrow glyph pointing in this direction.
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::Up => '↑',
+            Direction::Down => '↓',
+            Direction::Left => '←',
+            Direction::Right => '→',
+        }
+    }
+}
+
+/// The way the navigator wraps around when it leaves the playfield
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WrapMode {
+    /// Wrap around the edges of the padded playfield rectangle, as in Befunge-93.
+    #[default]
+    Rectangular,
+    /// Wrap around the bounding box of the content, as in Funge-98.
+    ///
+    /// When the next position lies outside of the content bounds, the navigator travels
+    /// backwards along its delta to the far side of the bounds instead.
+    LaheySpace,
+}
+
 /// A navigator through the playfield
 ///
-/// The navigator stores the current position and the direction at which we are looking.
+/// The navigator stores the current position and the delta by which it moves in each step.
+/// Usually, the delta is a unit vector pointing in one of the four cardinal directions, but
+/// Funge-98 allows arbitrary deltas.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PlayfieldNavigator {
     dim: (usize, usize),
     pos: (usize, usize),
-    dir: Direction,
+    delta: (i64, i64),
+    wrap: WrapMode,
+    bounds: Option<((usize, usize), (usize, usize))>,
+    wraps: u64,
 }
 
 impl PlayfieldNavigator {
@@ -106,50 +787,92 @@ impl PlayfieldNavigator {
         Self {
             dim,
             pos: (0, 0),
-            dir: Direction::Right,
+            delta: Direction::Right.delta(),
+            wrap: WrapMode::Rectangular,
+            bounds: None,
+            wraps: 0,
         }
     }
 
     /// Move one step in the field.
     ///
     /// When the border of the field is reached, the navigator wraps around and continues at the
-    /// opposite side of the field.
+    /// opposite side of the field. See [`WrapMode`] for wrapping around the content bounds.
+    ///
+    /// [`WrapMode`]: enum.WrapMode.html
     pub fn step(&mut self) {
-        match self.dir {
-            Direction::Up => {
-                if self.pos.1 > 0 {
-                    self.pos.1 -= 1
-                } else {
-                    self.pos.1 = self.dim.1 - 1
-                }
-            }
-            Direction::Down => {
-                if self.pos.1 < self.dim.1 - 1 {
-                    self.pos.1 += 1
-                } else {
-                    self.pos.1 = 0
-                }
-            }
-            Direction::Left => {
-                if self.pos.0 > 0 {
-                    self.pos.0 -= 1
-                } else {
-                    self.pos.0 = self.dim.0 - 1
-                }
-            }
-            Direction::Right => {
-                if self.pos.0 < self.dim.0 - 1 {
-                    self.pos.0 += 1
-                } else {
-                    self.pos.0 = 0
-                }
+        // Deltas are arbitrary in Funge-98, so positions are computed without overflowing
+        let next = (
+            self.pos.0 as i128 + i128::from(self.delta.0),
+            self.pos.1 as i128 + i128::from(self.delta.1),
+        );
+
+        self.pos = match (self.wrap, self.bounds) {
+            (WrapMode::LaheySpace, Some(bounds))
+                if contains(bounds, self.pos) && self.delta != (0, 0) =>
+            {
+                lahey_wrap(self.pos, self.delta, bounds)
             }
+            _ => (
+                wrap(self.pos.0, self.delta.0, self.dim.0),
+                wrap(self.pos.1, self.delta.1, self.dim.1),
+            ),
+        };
+
+        if (self.pos.0 as i128, self.pos.1 as i128) != next {
+            self.wraps += 1;
         }
     }
 
+    /// Return how many steps wrapped around an edge so far.
+    pub fn wrap_count(&self) -> u64 {
+        self.wraps
+    }
+
+    /// Return the dimensions the navigator wraps around.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dim
+    }
+
+    /// Set the dimensions the navigator wraps around, e.g. after resizing the playfield.
+    ///
+    /// The position is clamped to the new dimensions.
+    pub fn set_dimensions(&mut self, dim: (usize, usize)) {
+        self.dim = dim;
+        self.pos = (
+            self.pos.0.min(dim.0.saturating_sub(1)),
+            self.pos.1.min(dim.1.saturating_sub(1)),
+        );
+    }
+
+    /// Return the wrap mode of the navigator.
+    pub fn wrap_mode(&self) -> WrapMode {
+        self.wrap
+    }
+
+    /// Set the wrap mode and the content bounds used for wrapping in Lahey-space.
+    ///
+    /// The bounds are given by their inclusive corners, see [`Playfield::content_bounds`].
+    /// Outside of the bounds or without any bounds, the navigator wraps rectangularly.
+    ///
+    /// [`Playfield::content_bounds`]: struct.Playfield.html#method.content_bounds
+    pub fn set_wrap_mode(
+        &mut self,
+        wrap: WrapMode,
+        bounds: Option<((usize, usize), (usize, usize))>,
+    ) {
+        self.wrap = wrap;
+        self.bounds = bounds;
+    }
+
     /// Turn into the given direction.
     pub fn turn(&mut self, dir: Direction) {
-        self.dir = dir
+        self.delta = dir.delta()
+    }
+
+    /// Set an arbitrary delta.
+    pub fn set_delta(&mut self, delta: (i64, i64)) {
+        self.delta = delta
     }
 
     /// Return the current position of the navigator.
@@ -157,16 +880,244 @@ impl PlayfieldNavigator {
         self.pos
     }
 
+    /// Return the current delta of the navigator.
+    pub fn delta(&self) -> (i64, i64) {
+        self.delta
+    }
+
     /// Return the current direction the navigator is looking in.
-    pub fn dir(&self) -> Direction {
-        self.dir
+    ///
+    /// Returns `None` if the delta does not point in one of the four cardinal directions.
+    pub fn dir(&self) -> Option<Direction> {
+        Direction::from_delta(self.delta)
+    }
+
+    /// Return an arrow glyph for the current delta.
+    ///
+    /// The arrow points along the axis the delta moves the most on. If the delta is zero or
+    /// moves equally far on both axes, `'•'` is returned instead.
+    pub fn delta_arrow(&self) -> char {
+        let (dx, dy) = self.delta;
+
+        let dir = if dx.unsigned_abs() > dy.unsigned_abs() {
+            if dx > 0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if dy.unsigned_abs() > dx.unsigned_abs() {
+            if dy > 0 {
+                Direction::Down
+            } else {
+                Direction::Up
+            }
+        } else {
+            return '•';
+        };
+
+        dir.arrow()
+    }
+}
+
+fn wrap(pos: usize, delta: i64, dim: usize) -> usize {
+    (pos as i128 + i128::from(delta)).rem_euclid(dim as i128) as usize
+}
+
+fn contains(bounds: ((usize, usize), (usize, usize)), pos: (usize, usize)) -> bool {
+    let ((x0, y0), (x1, y1)) = bounds;
+
+    (x0..=x1).contains(&pos.0) && (y0..=y1).contains(&pos.1)
+}
+
+fn lahey_wrap(
+    pos: (usize, usize),
+    delta: (i64, i64),
+    bounds: ((usize, usize), (usize, usize)),
+) -> (usize, usize) {
+    let offset = |pos: (usize, usize), n: i128| {
+        let x = usize::try_from(pos.0 as i128 + n * i128::from(delta.0)).ok()?;
+        let y = usize::try_from(pos.1 as i128 + n * i128::from(delta.1)).ok()?;
+
+        Some((x, y)).filter(|&p| contains(bounds, p))
+    };
+
+    if let Some(next) = offset(pos, 1) {
+        return next;
+    }
+
+    // travel backwards until the next step would leave the bounds
+    let mut pos = pos;
+
+    while let Some(prev) = offset(pos, -1) {
+        pos = prev;
     }
+
+    pos
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn wide_cells() {
+        let mut playfield = Playfield::new("ab\ncd");
+
+        playfield.set_value((0, 0), 1000);
+        assert_eq!(232, playfield.value((0, 0)));
+
+        playfield.set_cell_type(CellType::Wide);
+        playfield.set_value((0, 0), 1000);
+        playfield.set_value((1, 1), -1);
+
+        assert_eq!(CellType::Wide, playfield.cell_type());
+        assert_eq!(1000, playfield.value((0, 0)));
+        assert_eq!(-1, playfield.value((1, 1)));
+        assert_eq!(255, playfield[(1, 1)]);
+        assert_eq!(i64::from(b'b'), playfield.value((1, 0)));
+
+        playfield[(0, 0)] = b'x';
+        assert_eq!(i64::from(b'x'), playfield.value((0, 0)));
+
+        playfield.resize(1, 1);
+        playfield.resize(2, 2);
+        assert_eq!(i64::from(b' '), playfield.value((1, 1)));
+
+        playfield.set_value((0, 0), 1000);
+        playfield.set_cell_type(CellType::Byte);
+        assert_eq!(232, playfield.value((0, 0)));
+    }
+
+    #[test]
+    fn generate() {
+        let playfield = Playfield::generate(12, 3, |x, y| (x + y) as u8 % 10 + b'0');
+
+        assert_eq!((12, 3), playfield.dimensions());
+        assert_eq!(b'0', playfield[(0, 0)]);
+        assert_eq!(b'5', playfield[(3, 2)]);
+        assert_eq!(b'1', playfield[(11, 0)]);
+        assert_eq!(b'3', playfield[(11, 2)]);
+        assert!(!playfield.is_padding((11, 2)));
+
+        assert_eq!((1, 1), Playfield::generate(0, 0, |_, _| b'@').dimensions());
+    }
+
+    #[test]
+    fn is_deterministic() {
+        assert!(Playfield::new("23*.@").is_deterministic());
+        assert!(Playfield::new("\"!dlrow\",,,,,,@").is_deterministic());
+        assert!(!Playfield::new("v\n?.@").is_deterministic());
+        assert!(!Playfield::new("~,@").is_deterministic());
+        assert!(!Playfield::new("&.@").is_deterministic());
+    }
+
+    #[test]
+    fn density() {
+        let playfield = Playfield::new("@\n\n\n      1");
+
+        assert_eq!(2, playfield.nonspace_count());
+        assert_eq!(2.0 / 28.0, playfield.density());
+        assert!(playfield.density() < 0.1);
+
+        let playfield = Playfield::new("12\n3@");
+
+        assert_eq!(4, playfield.nonspace_count());
+        assert_eq!(1.0, playfield.density());
+
+        let playfield = Playfield::new("   ");
+
+        assert_eq!(0, playfield.nonspace_count());
+        assert_eq!(0.0, playfield.density());
+    }
+
+    #[test]
+    fn swap_cells() {
+        let mut playfield = Playfield::new("ab\ncd");
+        playfield.set_edit_log(true);
+
+        playfield.swap_cells((0, 0), (1, 1));
+        assert_eq!("db\nca\n", playfield.to_string());
+        assert_eq!(2, playfield.take_edits().len());
+
+        playfield.swap_cells((1, 0), (1, 0));
+        assert_eq!("db\nca\n", playfield.to_string());
+        assert!(playfield.take_edits().is_empty());
+
+        playfield.set_cell_type(CellType::Wide);
+        playfield.set_value((0, 1), 1000);
+        playfield.swap_cells((0, 1), (1, 0));
+
+        assert_eq!(1000, playfield.value((1, 0)));
+        assert_eq!(i64::from(b'b'), playfield.value((0, 1)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_cells_out_of_bounds() {
+        Playfield::new("ab").swap_cells((0, 0), (2, 0));
+    }
+
+    #[test]
+    fn padding_cells() {
+        let mut playfield = Playfield::new("ab\ncde");
+
+        assert!(!playfield.is_padding((1, 0)));
+        assert!(playfield.is_padding((2, 0)));
+        assert_eq!(i64::from(b' '), playfield.value((2, 0)));
+
+        playfield.set_padding_value(PaddingValue::Zero);
+        playfield.resize(4, 2);
+
+        assert_eq!(0, playfield.value((2, 0)));
+        assert_eq!(0, playfield.value((3, 1)));
+        assert_eq!(i64::from(b'e'), playfield.value((2, 1)));
+
+        playfield.set((2, 0), b' ');
+
+        assert!(!playfield.is_padding((2, 0)));
+        assert_eq!(i64::from(b' '), playfield.value((2, 0)));
+        assert!(!playfield.trimmed().is_padding((2, 0)));
+    }
+
+    #[test]
+    fn navigator_dimensions() {
+        let mut navigator = PlayfieldNavigator::new((3, 3));
+
+        navigator.set_delta((1, 1));
+        navigator.step();
+        navigator.step();
+        assert_eq!((2, 2), navigator.pos());
+
+        navigator.set_dimensions((2, 5));
+        assert_eq!((2, 5), navigator.dimensions());
+        assert_eq!((1, 2), navigator.pos());
+
+        navigator.step();
+        assert_eq!((0, 3), navigator.pos());
+    }
+
+    #[test]
+    fn navigator_wrap_count() {
+        let mut navigator = PlayfieldNavigator::new((3, 2));
+
+        navigator.step();
+        navigator.step();
+        assert_eq!(0, navigator.wrap_count());
+
+        navigator.step();
+        assert_eq!((0, 0), navigator.pos());
+        assert_eq!(1, navigator.wrap_count());
+
+        navigator.set_delta((0, -1));
+        navigator.step();
+        navigator.step();
+        assert_eq!(2, navigator.wrap_count());
+
+        navigator.set_delta((0, 0));
+        navigator.step();
+        assert_eq!(2, navigator.wrap_count());
+    }
+
     #[test]
     fn playfield() {
         let mut playfield = Playfield::new("abc\nde\nx yz\n");
@@ -182,11 +1133,373 @@ mod tests {
         assert_eq!('b', playfield[(3, 1)] as char);
     }
 
+    #[test]
+    fn playfield_width_height() {
+        let playfield = Playfield::new("v  <\n>@\n^ \n");
+
+        assert_eq!(4, playfield.width());
+        assert_eq!(3, playfield.height());
+        assert_eq!(
+            (playfield.width(), playfield.height()),
+            playfield.dimensions()
+        );
+    }
+
+    #[test]
+    fn playfield_whitespace_input() {
+        assert_eq!((1, 1), Playfield::new("").dimensions());
+        assert_eq!((1, 1), Playfield::new("\n").dimensions());
+        assert_eq!((1, 1), Playfield::new("@\n").dimensions());
+        assert_eq!((1, 2), Playfield::new("@\n\n").dimensions());
+        assert_eq!("@\n \n", Playfield::new("@\n\n").to_string());
+
+        let mut navigator = PlayfieldNavigator::new(Playfield::new("\n").dimensions());
+
+        navigator.step();
+        assert_eq!((0, 0), navigator.pos());
+    }
+
+    #[test]
+    fn playfield_empty_program() {
+        for input in &["", "\n\n\n"] {
+            let playfield = Playfield::new(input);
+            let height = input.lines().count().max(1);
+
+            assert_eq!((1, height), playfield.dimensions());
+            assert_eq!(" \n".repeat(height), playfield.to_string());
+            assert_eq!(0, playfield.nonspace_count());
+
+            let mut navigator = PlayfieldNavigator::new(playfield.dimensions());
+
+            for _ in 0..height {
+                assert_eq!(b' ', playfield[navigator.pos()]);
+                navigator.step();
+            }
+
+            assert_eq!((0, 0), navigator.pos());
+        }
+    }
+
+    #[test]
+    fn playfield_from_path() {
+        let playfield = Playfield::from_path("examples/hello_world.bf").unwrap();
+
+        assert!(playfield.width() > 0);
+        assert!(Playfield::from_path("examples/does_not_exist.bf").is_err());
+    }
+
+    #[test]
+    fn program_size() {
+        assert!(check_size(1 << 40, None).is_ok());
+        assert!(check_size(100, Some(100)).is_ok());
+        assert!(matches!(
+            check_size(101, Some(100)),
+            Err(LoadError::TooLarge {
+                len: 101,
+                max_size: 100
+            })
+        ));
+    }
+
+    #[test]
+    fn playfield_from_path_with_limit() {
+        let path = "examples/hello_world.bf";
+        let len = std::fs::metadata(path).unwrap().len();
+
+        assert!(Playfield::from_path_with_limit(path, Some(len)).is_ok());
+
+        let e = Playfield::from_path_with_limit(path, Some(len - 1)).unwrap_err();
+
+        assert!(matches!(e, LoadError::TooLarge { .. }));
+        assert_eq!(
+            format!(
+                "program too large: {} bytes, but at most {} are allowed",
+                len,
+                len - 1
+            ),
+            e.to_string()
+        );
+        assert_eq!(format!("{}: {}", path, e), e.describe(path));
+
+        let path = "examples/does_not_exist.bf";
+        let e = Playfield::from_path_with_limit(path, None).unwrap_err();
+
+        assert!(matches!(e, LoadError::Open(_)));
+        assert!(e.to_string().starts_with("could not open: "));
+        assert!(e
+            .describe(path)
+            .starts_with("could not open examples/does_not_exist.bf: "));
+    }
+
+    #[test]
+    fn playfield_tabs() {
+        let input = "a\tb\n\tc\n";
+
+        let playfield = Playfield::new(input);
+
+        assert_eq!((3, 2), playfield.dimensions());
+        assert_eq!("a\tb\n\tc \n", playfield.to_string());
+
+        let options = LoadOptions { tab_width: Some(4) };
+        let playfield = Playfield::new_with_options(input, &options);
+
+        assert_eq!((5, 2), playfield.dimensions());
+        assert_eq!("a   b\n    c\n", playfield.to_string());
+    }
+
+    #[test]
+    fn playfield_read_region() {
+        let playfield = Playfield::new("abc\ndef\nghi");
+
+        assert_eq!(
+            vec![b"ef".to_vec(), b"hi".to_vec()],
+            playfield.read_region(1, 1, 2, 2)
+        );
+        assert_eq!(vec![b"i".to_vec()], playfield.read_region(2, 2, 5, 5));
+        assert!(playfield.read_region(3, 2, 2, 2).is_empty());
+        assert!(playfield.read_region(0, 3, 2, 2).is_empty());
+    }
+
+    #[test]
+    fn playfield_write_region() {
+        let mut playfield = Playfield::new("abc\ndef\nghi");
+
+        playfield.write_region(1, 1, &[b"xyz".to_vec(), b"uvw".to_vec(), b"rst".to_vec()]);
+
+        assert_eq!("abc\ndxy\nguv\n", playfield.to_string());
+    }
+
+    #[test]
+    fn playfield_resize() {
+        let mut playfield = Playfield::new("abc\ndef");
+
+        playfield.resize(4, 3);
+        assert_eq!("abc \ndef \n    \n", playfield.to_string());
+
+        playfield.resize(2, 1);
+        assert_eq!("ab\n", playfield.to_string());
+
+        playfield.resize(0, 0);
+        assert_eq!((1, 1), playfield.dimensions());
+    }
+
+    #[test]
+    fn playfield_put() {
+        let mut playfield = Playfield::new("ab\ncd");
+
+        assert_eq!(Some((1, 0)), playfield.put(1, 0, i64::from(b'x'), 0));
+        assert_eq!((2, 2), playfield.dimensions());
+
+        assert_eq!(None, playfield.put(100, 0, i64::from(b'y'), 201));
+        assert_eq!(Some((100, 0)), playfield.put(100, 0, i64::from(b'y'), 202));
+        assert_eq!((101, 2), playfield.dimensions());
+        assert_eq!(b'y', playfield[(100, 0)]);
+        assert_eq!(b' ', playfield[(50, 1)]);
+        assert_eq!("ax", &playfield.to_string()[..2]);
+
+        assert_eq!(
+            Some((0, 3)),
+            playfield.put(0, 3, i64::from(b'z'), usize::MAX)
+        );
+        assert_eq!((101, 4), playfield.dimensions());
+
+        assert_eq!(None, playfield.put(-1, 0, 0, usize::MAX));
+        assert_eq!(None, playfield.put(0, i64::MIN, 0, usize::MAX));
+        assert_eq!(None, playfield.put(i64::MAX, i64::MAX, 0, usize::MAX));
+        assert_eq!((101, 4), playfield.dimensions());
+    }
+
+    #[test]
+    fn playfield_apply_patch() {
+        let mut playfield = Playfield::new("abc\ndef");
+
+        playfield.apply_patch(&[(0, 0, b'x'), (5, 0, b'y'), (1, 1, b'z')], false);
+        assert_eq!("xbc\ndzf\n", playfield.to_string());
+
+        playfield.set_edit_log(true);
+        playfield.apply_patch(&[(2, 0, b'1'), (4, 2, b'2')], true);
+
+        assert_eq!((5, 3), playfield.dimensions());
+        assert_eq!("xb1  \ndzf  \n    2\n", playfield.to_string());
+        assert_eq!(2, playfield.take_edits().len());
+    }
+
+    #[test]
+    fn playfield_edit_log() {
+        let mut playfield = Playfield::new("abc\ndef");
+
+        playfield.set((0, 0), b'x');
+        assert!(playfield.take_edits().is_empty());
+
+        playfield.set_edit_log(true);
+        playfield.set((1, 0), b'y');
+        playfield.write_region(2, 1, &[b"zz".to_vec()]);
+        playfield[(0, 1)] = b'w';
+
+        let edit = |pos, old, new| Edit { pos, old, new };
+
+        assert_eq!(
+            vec![edit((1, 0), b'b', b'y'), edit((2, 1), b'f', b'z')],
+            playfield.take_edits()
+        );
+        assert!(playfield.take_edits().is_empty());
+        assert_eq!("xyc\nwez\n", playfield.to_string());
+    }
+
+    #[test]
+    fn playfield_instruction_histogram() {
+        let playfield = Playfield::new("12+v\n  x@ \n\"a\"");
+        let histogram = playfield.instruction_histogram();
+
+        assert_eq!(Some(&2), histogram.get(&InstructionKind::Number));
+        assert_eq!(Some(&1), histogram.get(&InstructionKind::Operator));
+        assert_eq!(Some(&1), histogram.get(&InstructionKind::Movement));
+        assert_eq!(Some(&1), histogram.get(&InstructionKind::Terminate));
+        assert_eq!(Some(&2), histogram.get(&InstructionKind::StringMode));
+        assert_eq!(Some(&2), histogram.get(&InstructionKind::Illegal));
+        assert_eq!(None, histogram.get(&InstructionKind::NoOp));
+        assert_eq!(9, histogram.values().sum::<usize>());
+    }
+
+    #[test]
+    fn playfield_content_bounds() {
+        let playfield = Playfield::new("     \n  a  \n b c \n     ");
+
+        assert_eq!(Some(((1, 1), (3, 2))), playfield.content_bounds());
+        assert_eq!(None, Playfield::new("  \n  ").content_bounds());
+        assert_eq!(Some(((0, 0), (0, 0))), Playfield::new("x").content_bounds());
+    }
+
+    #[test]
+    fn playfield_trimmed() {
+        let playfield = Playfield::new("     \n  a  \n b c \n     ");
+        let trimmed = playfield.trimmed();
+
+        assert_eq!((3, 2), trimmed.dimensions());
+        assert_eq!(" a \nb c\n", trimmed.to_string());
+        assert_eq!((1, 1), Playfield::new("  \n  ").trimmed().dimensions());
+    }
+
+    #[test]
+    fn playfield_render_with_cursor() {
+        let playfield = Playfield::new("abc\ndef");
+
+        assert_eq!("abc\ndef\n  ^\n", playfield.render_with_cursor((2, 1)));
+        assert_eq!("abc\n^\ndef\n", playfield.render_with_cursor((0, 0)));
+    }
+
+    #[test]
+    fn playfield_display_non_utf8() {
+        let mut playfield = Playfield::new("ab");
+
+        playfield[(1, 0)] = 200;
+
+        assert_eq!("a\u{fffd}\n", playfield.to_string());
+    }
+
+    #[test]
+    fn playfield_large() {
+        let input: String = (0..10_000)
+            .map(|i| format!("{}\n", "v".repeat(i % 80 + 1)))
+            .collect();
+
+        let playfield = Playfield::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!((80, 10_000), playfield.dimensions());
+        assert_eq!(b'v', playfield[(0, 0)]);
+        assert_eq!(b' ', playfield[(1, 0)]);
+        assert_eq!(b'v', playfield[(79, 9_999)]);
+        assert_eq!(b'v', playfield[(40, 9_960)]);
+        assert_eq!(b' ', playfield[(41, 9_960)]);
+    }
+
+    #[test]
+    fn direction_u8() {
+        for dir in &[
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            assert_eq!(Some(*dir), Direction::from_u8(dir.as_u8()));
+        }
+
+        assert_eq!(None, Direction::from_u8(4));
+    }
+
+    #[test]
+    fn direction_arrow() {
+        assert_eq!('↑', Direction::Up.arrow());
+        assert_eq!('↓', Direction::Down.arrow());
+        assert_eq!('←', Direction::Left.arrow());
+        assert_eq!('→', Direction::Right.arrow());
+
+        let mut navigator = PlayfieldNavigator::new((10, 10));
+        assert_eq!('→', navigator.delta_arrow());
+
+        for (delta, arrow) in [
+            ((0, 0), '•'),
+            ((2, -2), '•'),
+            ((-3, 1), '←'),
+            ((1, -4), '↑'),
+            ((0, 2), '↓'),
+        ] {
+            navigator.set_delta(delta);
+            assert_eq!(arrow, navigator.delta_arrow());
+        }
+    }
+
+    #[test]
+    fn playfield_navigator_lahey_space() {
+        let playfield = Playfield::new("       \n  a  b \n       \n  c  d \n       ");
+        let bounds = playfield.content_bounds();
+
+        let mut navigator = PlayfieldNavigator::new(playfield.dimensions());
+        navigator.set_wrap_mode(WrapMode::LaheySpace, bounds);
+
+        // outside of the bounds, the navigator wraps rectangularly
+        navigator.turn(Direction::Down);
+        navigator.step();
+        assert_eq!((0, 1), navigator.pos());
+
+        navigator.turn(Direction::Right);
+        navigator.step();
+        navigator.step();
+        assert_eq!((2, 1), navigator.pos());
+
+        navigator.step();
+        navigator.step();
+        navigator.step();
+        assert_eq!((5, 1), navigator.pos());
+
+        navigator.step();
+        assert_eq!((2, 1), navigator.pos());
+
+        navigator.turn(Direction::Up);
+        navigator.step();
+        assert_eq!((2, 3), navigator.pos());
+
+        navigator.set_delta((3, -2));
+        navigator.step();
+        assert_eq!((5, 1), navigator.pos());
+
+        navigator.step();
+        assert_eq!((2, 3), navigator.pos());
+
+        navigator.step();
+        assert_eq!((5, 1), navigator.pos());
+
+        navigator.set_wrap_mode(WrapMode::Rectangular, bounds);
+        navigator.turn(Direction::Right);
+        navigator.step();
+        assert_eq!((6, 1), navigator.pos());
+    }
+
     #[test]
     fn playfield_navigator() {
         let mut navigator = PlayfieldNavigator::new((4, 3));
 
-        assert_eq!(Direction::Right, navigator.dir());
+        assert_eq!(Some(Direction::Right), navigator.dir());
         assert_eq!((0, 0), navigator.pos());
 
         navigator.step();
@@ -207,7 +1520,7 @@ mod tests {
 
         navigator.turn(Direction::Down);
 
-        assert_eq!(Direction::Down, navigator.dir());
+        assert_eq!(Some(Direction::Down), navigator.dir());
         assert_eq!((0, 0), navigator.pos());
 
         navigator.step();
@@ -224,7 +1537,7 @@ mod tests {
 
         navigator.turn(Direction::Left);
 
-        assert_eq!(Direction::Left, navigator.dir());
+        assert_eq!(Some(Direction::Left), navigator.dir());
         assert_eq!((0, 0), navigator.pos());
 
         navigator.step();
@@ -233,7 +1546,7 @@ mod tests {
 
         navigator.turn(Direction::Up);
 
-        assert_eq!(Direction::Up, navigator.dir());
+        assert_eq!(Some(Direction::Up), navigator.dir());
         assert_eq!((3, 0), navigator.pos());
 
         navigator.step();
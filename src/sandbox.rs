@@ -0,0 +1,112 @@
+//! Sandboxed execution of untrusted programs
+//!
+//! A sandboxed run never panics, blocks or performs real I/O, which makes it suitable for
+//! fuzzing the interpreter.
+
+use crate::interpreter::{Interpreter, NullInputOutput, StepError, StepOutcome};
+use crate::playfield::Playfield;
+use std::io::prelude::*;
+
+/// The outcome of a sandboxed run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SandboxOutcome {
+    /// The program terminated after the given number of steps.
+    Terminated { steps: usize },
+    /// The program was still running when the step limit was reached.
+    StepLimitReached,
+    /// The program encountered an error, e.g. an illegal instruction.
+    Failed(StepError),
+}
+
+/// Run the given program for at most `max_steps` steps without input and discarding output.
+///
+/// The program may consist of arbitrary bytes. Invalid UTF-8 is replaced before the playfield
+/// is created. The Befunge-93 dialect is used.
+pub fn run_sandboxed(program: &[u8], max_steps: usize) -> SandboxOutcome {
+    let playfield = Playfield::new(&String::from_utf8_lossy(program));
+    let mut interpreter = Interpreter::new(playfield, NullInputOutput::default());
+
+    run_limited(&mut interpreter, max_steps)
+}
+
+/// Run the given interpreter for at most `max_steps` steps and summarize how it ended.
+///
+/// Unlike [`run_sandboxed`], the caller decides about the playfield, the input and the output.
+///
+/// [`run_sandboxed`]: fn.run_sandboxed.html
+pub fn run_limited<R, W>(interpreter: &mut Interpreter<R, W>, max_steps: usize) -> SandboxOutcome
+where
+    R: BufRead,
+    W: Write,
+{
+    match interpreter.run_with_limit(max_steps) {
+        Ok(StepOutcome::Terminated) => SandboxOutcome::Terminated {
+            steps: interpreter.steps(),
+        },
+        Ok(_) => SandboxOutcome::StepLimitReached,
+        Err(err) => SandboxOutcome::Failed(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sandboxed() {
+        assert_eq!(
+            SandboxOutcome::Terminated { steps: 4 },
+            run_sandboxed(b"23*@", 100)
+        );
+        assert_eq!(SandboxOutcome::StepLimitReached, run_sandboxed(b">", 100));
+        assert_eq!(SandboxOutcome::StepLimitReached, run_sandboxed(b"", 100));
+        assert_eq!(
+            SandboxOutcome::Failed(StepError::IllegalInstruction {
+                pos: (1, 0),
+                instruction: b'x',
+            }),
+            run_sandboxed(b"1x@", 100)
+        );
+    }
+
+    #[test]
+    fn limited() {
+        use crate::interpreter::InputOutput;
+
+        let io = InputOutput::new(&b"5"[..], Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new("&.@"), io);
+
+        assert_eq!(
+            SandboxOutcome::Terminated { steps: 3 },
+            run_limited(&mut interpreter, 100)
+        );
+        assert_eq!(b"5 ", &interpreter.io().writer()[..]);
+
+        let io = InputOutput::new(&b""[..], Vec::new());
+        let mut interpreter = Interpreter::new(Playfield::new(">"), io);
+
+        assert_eq!(
+            SandboxOutcome::StepLimitReached,
+            run_limited(&mut interpreter, 100)
+        );
+    }
+
+    #[test]
+    fn sandboxed_arbitrary_bytes() {
+        let charset = b"0123456789+-*/%!`><^v?_|\":\\$.,#pg&~@ \n\xff\xc3x";
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..500 {
+            let mut program = Vec::new();
+
+            for _ in 0..64 {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                program.push(charset[(seed >> 33) as usize % charset.len()]);
+            }
+
+            if let SandboxOutcome::Terminated { steps } = run_sandboxed(&program, 1000) {
+                assert!(steps <= 1000);
+            }
+        }
+    }
+}
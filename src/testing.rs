@@ -0,0 +1,69 @@
+//! Helpers for testing Befunge programs
+//!
+//! These helpers panic on failure, so they can be used directly within `#[test]` functions.
+
+use crate::interpreter::{InputOutput, Interpreter, StepOutcome};
+use crate::playfield::Playfield;
+
+/// The maximum number of steps a program may take in [`assert_output`]
+///
+/// [`assert_output`]: fn.assert_output.html
+pub const MAX_STEPS: usize = 1_000_000;
+
+/// Assert that the given program terminates and writes the expected output on the given input.
+///
+/// # Panics
+///
+/// Panics if the program encounters an error, does not terminate within [`MAX_STEPS`] steps or
+/// writes different output. On mismatch, the message shows both outputs and the first differing
+/// position.
+///
+/// [`MAX_STEPS`]: constant.MAX_STEPS.html
+pub fn assert_output(program: &str, input: &str, expected_output: &str) {
+    let io = InputOutput::new(input.as_bytes(), Vec::new());
+    let mut interpreter = Interpreter::new(Playfield::new(program), io);
+
+    match interpreter.run_with_limit(MAX_STEPS) {
+        Ok(StepOutcome::Terminated) => (),
+        Ok(_) => panic!("program did not terminate within {} steps", MAX_STEPS),
+        Err(err) => panic!("program failed: {}", err),
+    }
+
+    let output = String::from_utf8_lossy(interpreter.io().writer());
+
+    if output != expected_output {
+        let pos = output
+            .chars()
+            .zip(expected_output.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        panic!(
+            "output differs at character {}\n  expected: {:?}\n    actual: {:?}",
+            pos, expected_output, output
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output() {
+        assert_output("23*.@", "", "6 ");
+        assert_output("&&+.@", "3 4", "7 ");
+    }
+
+    #[test]
+    #[should_panic(expected = "output differs at character 0")]
+    fn output_mismatch() {
+        assert_output("23*.@", "", "5 ");
+    }
+
+    #[test]
+    #[should_panic(expected = "did not terminate")]
+    fn output_endless() {
+        assert_output(">", "", "");
+    }
+}